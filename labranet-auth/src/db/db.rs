@@ -3,7 +3,7 @@ use mongodb::{ options::ClientOptions, Client, Database};
 use rocket::fairing::AdHoc;
 
 
-
+#[derive(Debug, Clone)]
 pub struct  MongoDB {
     pub(crate) database: Database,
 }
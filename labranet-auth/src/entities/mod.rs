@@ -1 +1,3 @@
-pub mod users;
\ No newline at end of file
+pub mod users;
+pub mod refresh_tokens;
+pub mod password_resets;
\ No newline at end of file
@@ -0,0 +1,14 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// A password reset token sent to a user. Only its hash is stored and it can be
+/// used once, before `expires_at`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PasswordReset {
+    pub _id: ObjectId,
+    pub user_id: ObjectId,
+    pub token_hash: String,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+    pub used_at: Option<DateTime>,
+}
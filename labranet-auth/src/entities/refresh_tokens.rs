@@ -0,0 +1,17 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// A refresh token handed to a client. Every refresh replaces the token with a new
+/// one of the same family, so a token that was already replaced and shows up again
+/// has been stolen and the whole family is revoked.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RefreshToken {
+    pub _id: ObjectId,
+    pub family_id: ObjectId,
+    pub user_id: ObjectId,
+    pub token_hash: String,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+    pub replaced_by: Option<ObjectId>,
+    pub revoked: bool,
+}
@@ -10,4 +10,7 @@ pub struct User {
     pub first_name: String,
     pub last_name: String,
     pub role: String,
+    /// A disabled user cannot log in or refresh tokens.
+    #[serde(default)]
+    pub disabled: bool,
 }
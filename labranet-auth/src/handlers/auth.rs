@@ -1,16 +1,46 @@
-use labranet_common::{jwt::JWT, response:: ResponseError};
-use rocket::{get, post, response::status::Custom, serde::json::Json, State};
+use labranet_common::{error::AppError, jwt::JWT, response::{Response, ResponseError}};
+use rocket::{get, patch, post, serde::json::Json, State};
 
-use crate::{models::login::LoginModel, usecases::users::UserUseCaseTrait};
+use crate::{models::{login::LoginModel, tokens::{RefreshModel, TokenModel}, users::{ForgotPasswordModel, PasswordModel, ProfileModel, ResetPasswordModel}}, usecases::users::UserUseCaseTrait, views::users::UserView};
 
 #[post("/auth/login", format = "application/json", data = "<login>")]
-pub async  fn login(user_use_case: &State<Box<dyn UserUseCaseTrait>>,login:Json<LoginModel>)->Result<String,Custom<String>>{
+pub async  fn login(user_use_case: &State<Box<dyn UserUseCaseTrait>>,login:Json<LoginModel>)->Result<Response<TokenModel>,AppError>{
  
     let result = user_use_case.login(login.into_inner()).await;
     result
 }
+#[post("/auth/refresh", format = "application/json", data = "<refresh>")]
+pub async fn refresh(user_use_case: &State<Box<dyn UserUseCaseTrait>>,refresh:Json<RefreshModel>)->Result<Response<TokenModel>,AppError>{
+    let result = user_use_case.refresh(refresh.into_inner()).await;
+    result
+}
+#[post("/auth/logout")]
+pub async fn logout(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<JWT,ResponseError<String>>)->Result<Response<String>,AppError>{
+    let result = user_use_case.logout(key).await;
+    result
+}
 #[get("/auth/current-user")]
-pub async fn current_user(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<JWT,ResponseError<String>>,)->Result<String,Custom<String>>{
+pub async fn current_user(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<JWT,ResponseError<String>>,)->Result<Response<UserView>,AppError>{
     let result = user_use_case.get_current_user(key).await;
     result
-}
\ No newline at end of file
+}
+#[patch("/auth/me", format = "application/json", data = "<profile>")]
+pub async fn update_profile(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<JWT,ResponseError<String>>,profile:Json<ProfileModel>)->Result<Response<UserView>,AppError>{
+    let result = user_use_case.update_profile(key, profile.into_inner()).await;
+    result
+}
+#[post("/auth/me/password", format = "application/json", data = "<password>")]
+pub async fn change_password(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<JWT,ResponseError<String>>,password:Json<PasswordModel>)->Result<Response<String>,AppError>{
+    let result = user_use_case.change_password(key, password.into_inner()).await;
+    result
+}
+#[post("/auth/password/forgot", format = "application/json", data = "<forgot>")]
+pub async fn forgot_password(user_use_case: &State<Box<dyn UserUseCaseTrait>>,forgot:Json<ForgotPasswordModel>)->Result<Response<String>,AppError>{
+    let result = user_use_case.forgot_password(forgot.into_inner()).await;
+    result
+}
+#[post("/auth/password/reset", format = "application/json", data = "<reset>")]
+pub async fn reset_password(user_use_case: &State<Box<dyn UserUseCaseTrait>>,reset:Json<ResetPasswordModel>)->Result<Response<String>,AppError>{
+    let result = user_use_case.reset_password(reset.into_inner()).await;
+    result
+}
@@ -0,0 +1,12 @@
+use std::sync::Arc;
+
+use jsonwebtoken::jwk::JwkSet;
+use rocket::{get, serde::json::Json, State};
+
+use crate::utils::keys::SigningKeys;
+
+/// Public keys the other services verify access tokens with.
+#[get("/.well-known/jwks.json")]
+pub async fn jwks(signing_keys: &State<Arc<SigningKeys>>) -> Json<JwkSet> {
+    Json(signing_keys.jwks().clone())
+}
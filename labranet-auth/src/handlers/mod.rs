@@ -1,2 +1,3 @@
 pub mod users;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod keys;
\ No newline at end of file
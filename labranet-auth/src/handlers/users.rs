@@ -1,19 +1,70 @@
 use rocket::State;
 use rocket::serde::json::Json;
+use labranet_common::{error::AppError, jwt::Authorized, pagination::{Page, PageQuery}, permissions::UsersAdmin, response::Response};
 use rocket::{
+    delete,
+    get,
+    patch,
     post,
-    response::status::{Created, Custom},
+    response::status::Created,
 };
 use crate::{
-    models::users::UserModel,
+    models::users::{RoleModel, SignUpModel, UserFilterModel, UserModel},
     usecases::users::UserUseCaseTrait,
+    views::users::UserView,
 };
 
 #[post("/users", format = "application/json", data = "<user>")]
-pub async fn sign_up(user_use_case: &State<Box<dyn UserUseCaseTrait>>,user: Json<UserModel>) -> Result<Created<String>, Custom<String>> {
+pub async fn sign_up(user_use_case: &State<Box<dyn UserUseCaseTrait>>,user: Json<SignUpModel>) -> Result<Created<Response<UserView>>, AppError> {
     
     let result = user_use_case.sign_up(user.into_inner()).await;
     result
 }
 
+#[post("/admin/users", format = "application/json", data = "<user>")]
+pub async fn create_user(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<Authorized<UsersAdmin>,AppError>,user: Json<UserModel>) -> Result<Created<Response<UserView>>, AppError> {
+    let result = user_use_case.create_user(key, user.into_inner()).await;
+    result
+}
+
+#[get("/admin/users?<page>&<limit>&<sort>&<filter..>")]
+pub async fn fetch_all_user(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<Authorized<UsersAdmin>,AppError>,page:Option<u64>,limit:Option<u64>,sort:Option<String>,filter:UserFilterModel) -> Result<Response<Page<UserView>>, AppError> {
+    let result = user_use_case.fetch_all_user(key, filter, PageQuery{page,limit,sort}).await;
+    result
+}
+
+#[get("/admin/users/<id>")]
+pub async fn fetch_one_user(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<Authorized<UsersAdmin>,AppError>,id:&str) -> Result<Response<UserView>, AppError> {
+    let result = user_use_case.fetch_one_user(key, id).await;
+    result
+}
+
+#[patch("/admin/users/<id>/role", format = "application/json", data = "<role>")]
+pub async fn change_role(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<Authorized<UsersAdmin>,AppError>,id:&str,role: Json<RoleModel>) -> Result<Response<UserView>, AppError> {
+    let result = user_use_case.change_role(key, id, role.into_inner()).await;
+    result
+}
 
+#[post("/admin/users/<id>/disable")]
+pub async fn disable_user(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<Authorized<UsersAdmin>,AppError>,id:&str) -> Result<Response<UserView>, AppError> {
+    let result = user_use_case.set_disabled(key, id, true).await;
+    result
+}
+
+#[post("/admin/users/<id>/enable")]
+pub async fn enable_user(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<Authorized<UsersAdmin>,AppError>,id:&str) -> Result<Response<UserView>, AppError> {
+    let result = user_use_case.set_disabled(key, id, false).await;
+    result
+}
+
+#[delete("/admin/users/<id>")]
+pub async fn delete_user(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<Authorized<UsersAdmin>,AppError>,id:&str) -> Result<Response<String>, AppError> {
+    let result = user_use_case.delete_user(key, id).await;
+    result
+}
+
+#[delete("/admin/users/<id>/sessions")]
+pub async fn revoke_sessions(user_use_case: &State<Box<dyn UserUseCaseTrait>>,key:Result<Authorized<UsersAdmin>,AppError>,id:&str) -> Result<Response<String>, AppError> {
+    let result = user_use_case.revoke_sessions(key, id).await;
+    result
+}
@@ -4,4 +4,6 @@ pub mod repositories;
 pub mod db;
 pub mod usecases;
 pub mod handlers;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod notifications;
+pub mod views;
\ No newline at end of file
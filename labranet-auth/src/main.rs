@@ -4,20 +4,50 @@ extern crate rocket;
 use labranet_auth::db::db::connect;
 use labranet_auth::db::db::MongoDB;
 use labranet_auth::handlers::auth::current_user;
+use labranet_auth::handlers::auth::forgot_password;
+use labranet_auth::handlers::keys::jwks;
+use labranet_auth::handlers::auth::change_password;
 use labranet_auth::handlers::auth::login;
+use labranet_auth::handlers::auth::logout;
+use labranet_auth::handlers::auth::refresh;
+use labranet_auth::handlers::auth::reset_password;
+use labranet_auth::handlers::auth::update_profile;
+use labranet_auth::handlers::users::change_role;
+use labranet_auth::handlers::users::create_user;
+use labranet_auth::handlers::users::delete_user;
+use labranet_auth::handlers::users::disable_user;
+use labranet_auth::handlers::users::enable_user;
+use labranet_auth::handlers::users::fetch_all_user;
+use labranet_auth::handlers::users::fetch_one_user;
+use labranet_auth::handlers::users::revoke_sessions;
 use labranet_auth::handlers::users::sign_up;
+use labranet_auth::notifications;
+use labranet_auth::repositories::password_resets::PasswordResetRepo;
+use labranet_auth::repositories::password_resets::PasswordResetRepoTrait;
+use labranet_auth::repositories::refresh_tokens::RefreshTokenRepo;
+use labranet_auth::repositories::refresh_tokens::RefreshTokenRepoTrait;
 use labranet_auth::repositories::users::UserRepo;
 use labranet_auth::repositories::users::UserRepoTrait;
+use labranet_auth::usecases::users::AdminSeed;
 use labranet_auth::usecases::users::UserUseCase;
 use labranet_auth::usecases::users::UserUseCaseTrait;
+use labranet_auth::utils::keys::SigningKeys;
+use labranet_auth::utils::tokens::TokenConfig;
+use labranet_common::error::AppError;
+use labranet_common::events;
+use labranet_common::events::Publisher;
+use labranet_common::jwks::PublicKeys;
 use labranet_common::response::ResponseError;
 use labranet_common::response::ResponseErrorBody;
+use labranet_common::revocations::listen_revocations;
+use labranet_common::revocations::Denylist;
 use rocket::http::Method;
 use rocket::serde::json::Json;
 use rocket::shield::Allow;
 use rocket::shield::Permission;
 use rocket::shield::Shield;
 use rocket_cors::{AllowedOrigins, CorsOptions};
+use std::sync::Arc;
 
 
 
@@ -39,50 +69,73 @@ async fn rocket() -> _ {
         )
         .allow_credentials(true);
     let database = connect().await.unwrap();
+    let event_bus = events::connect().await.unwrap();
+    let publisher = Publisher::new(event_bus.clone());
+    let denylist = Arc::new(Denylist::new());
+    listen_revocations(event_bus, "labranet-auth", denylist.clone()).await.unwrap();
+    let signing_keys = Arc::new(SigningKeys::from_env());
+    let public_keys = PublicKeys::from_set(signing_keys.jwks());
     let mongo = MongoDB::new(database);
-    let user_repo : Box<dyn UserRepoTrait>=Box::new(UserRepo::new(mongo));
-    let user_usecase: Box<dyn UserUseCaseTrait> = Box::new(UserUseCase::new(user_repo));
+    let user_repo : Box<dyn UserRepoTrait>=Box::new(UserRepo::new(mongo.clone()));
+    let token_repo : Box<dyn RefreshTokenRepoTrait>=Box::new(RefreshTokenRepo::new(mongo.clone()));
+    let reset_repo : Box<dyn PasswordResetRepoTrait>=Box::new(PasswordResetRepo::new(mongo));
+    let user_usecase: Box<dyn UserUseCaseTrait> = Box::new(UserUseCase::new(user_repo,token_repo,reset_repo,notifications::from_env().unwrap(),TokenConfig::from_env(),signing_keys.clone(),publisher,denylist.clone()));
+    user_usecase.seed_admin(AdminSeed::from_env()).await.unwrap();
     rocket::build()
         .attach(Shield::default().enable(permission))
         .manage(user_usecase)
+        .manage(denylist)
+        .manage(signing_keys)
+        .manage(public_keys)
         .manage(cors.to_cors())
         .mount(
             "/api/v1",
             routes![
                 sign_up,
+                create_user,
+                fetch_all_user,
+                fetch_one_user,
+                change_role,
+                disable_user,
+                enable_user,
+                delete_user,
                 login,
-                current_user
+                refresh,
+                logout,
+                current_user,
+                update_profile,
+                change_password,
+                forgot_password,
+                reset_password,
+                revoke_sessions
                 
             ],
         )
+        .mount("/", routes![jwks])
         .register("/", 
-        catchers![unauthorized,not_found,internal_sever_error,bad_gateway]
+        catchers![unauthorized,forbidden,not_found,internal_sever_error,bad_gateway]
     )
        
 }
 
 #[catch(401)]
-pub fn unauthorized() -> Json<ResponseError<String>> {
-    let response = ResponseError{
-        error:ResponseErrorBody::<String>::Error("Unauthorize".to_string())
-    };
-    Json(response)
+pub fn unauthorized() -> AppError {
+    AppError::Unauthorized("Unauthorize".to_string())
+}
+
+#[catch(403)]
+pub fn forbidden() -> AppError {
+    AppError::Forbidden("Forbidden".to_string())
 }
 
 #[catch(404)]
-pub fn not_found() -> Json<ResponseError<String>> {
-    let response = ResponseError{
-        error:ResponseErrorBody::<String>::Error("Not Found".to_string())
-    };
-    Json(response)
+pub fn not_found() -> AppError {
+    AppError::NotFound("Not Found".to_string())
 }
 
 #[catch(500)]
-pub fn internal_sever_error() -> Json<ResponseError<String>> {
-    let response = ResponseError{
-        error:ResponseErrorBody::<String>::Error("UNKNOWN Json".to_string())
-    };
-    Json(response)
+pub fn internal_sever_error() -> AppError {
+    AppError::Internal("UNKNOWN Json".to_string())
 }
 
 #[catch(502)]
@@ -1,2 +1,3 @@
 pub mod users;
 pub mod login;
+pub mod tokens;
\ No newline at end of file
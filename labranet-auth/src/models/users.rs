@@ -1,12 +1,96 @@
 use rocket::serde::{Deserialize, Serialize};
+use rocket::FromForm;
 use labranet_common::roles::Role;
+use labranet_common::validation::phone;
+use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct UserModel {
+    #[validate(email(message = "Email must be a valid email address"))]
+    pub email: String,
+    #[validate(custom(function = "phone", message = "Mobile must be a valid phone number"))]
+    pub mobile: String,
+    #[validate(length(min = 8, max = 128, message = "Password must be between 8 and 128 characters"))]
+    pub password: String,
+    #[validate(length(min = 1, max = 100, message = "First Name is required and at most 100 characters"))]
+    pub first_name: String,
+    #[validate(length(min = 1, max = 100, message = "Last Name is required and at most 100 characters"))]
+    pub last_name: String,
+    pub role: Role,
+}
+
+/// What anyone may send to `POST /users`; the account is always a `Worker`.
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct SignUpModel {
+    #[validate(email(message = "Email must be a valid email address"))]
     pub email: String,
+    #[validate(custom(function = "phone", message = "Mobile must be a valid phone number"))]
     pub mobile: String,
+    #[validate(length(min = 8, max = 128, message = "Password must be between 8 and 128 characters"))]
     pub password: String,
+    #[validate(length(min = 1, max = 100, message = "First Name is required and at most 100 characters"))]
     pub first_name: String,
+    #[validate(length(min = 1, max = 100, message = "Last Name is required and at most 100 characters"))]
     pub last_name: String,
+}
+impl SignUpModel {
+    pub fn into_user(self, role: Role) -> UserModel {
+        UserModel {
+            email: self.email,
+            mobile: self.mobile,
+            password: self.password,
+            first_name: self.first_name,
+            last_name: self.last_name,
+            role,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, FromForm)]
+pub struct UserFilterModel {
+    pub email: Option<String>,
+    pub role: Option<String>,
+    pub disabled: Option<bool>,
+    /// Part of the email, first or last name, ignoring case.
+    pub search: Option<String>,
+}
+
+/// The fields a user may change on their own account; missing ones stay as they are.
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct ProfileModel {
+    #[validate(email(message = "Email must be a valid email address"))]
+    pub email: Option<String>,
+    #[validate(custom(function = "phone", message = "Mobile must be a valid phone number"))]
+    pub mobile: Option<String>,
+    #[validate(length(min = 1, max = 100, message = "First Name is required and at most 100 characters"))]
+    pub first_name: Option<String>,
+    #[validate(length(min = 1, max = 100, message = "Last Name is required and at most 100 characters"))]
+    pub last_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct PasswordModel {
+    #[validate(length(min = 1, message = "Current Password is required"))]
+    pub current_password: String,
+    #[validate(length(min = 8, max = 128, message = "Password must be between 8 and 128 characters"))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct ForgotPasswordModel {
+    #[validate(email(message = "Email must be a valid email address"))]
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+pub struct ResetPasswordModel {
+    #[validate(length(min = 1, message = "Reset token is required"))]
+    pub token: String,
+    #[validate(length(min = 8, max = 128, message = "Password must be between 8 and 128 characters"))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoleModel {
     pub role: Role,
 }
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use mongodb::bson::DateTime;
+use rocket::async_trait;
+use rocket::tokio::{fs::OpenOptions, io::AsyncWriteExt};
+
+use super::{NotificationError, NotificationSender};
+
+/// Writes every notification as a line to `path`, or to the log without one.
+pub struct FileSender {
+    path: Option<PathBuf>,
+}
+impl FileSender {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        FileSender { path }
+    }
+
+    async fn write(&self, line: String) -> Result<(), NotificationError> {
+        match &self.path {
+            Some(path) => {
+                let mut file = OpenOptions::new().create(true).append(true).open(path).await?;
+                file.write_all(format!("{}\n", line).as_bytes()).await?;
+            }
+            None => println!("{}", line),
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NotificationSender for FileSender {
+    async fn send_password_reset(
+        &self,
+        email: &str,
+        token: &str,
+        expires_at: DateTime,
+    ) -> Result<(), NotificationError> {
+        let expires_at = expires_at.try_to_rfc3339_string().unwrap_or_default();
+        self.write(format!(
+            "[{}] password reset for {}: token {} valid until {}",
+            DateTime::now().try_to_rfc3339_string().unwrap_or_default(),
+            email,
+            token,
+            expires_at
+        ))
+        .await
+    }
+}
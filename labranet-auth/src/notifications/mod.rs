@@ -0,0 +1,77 @@
+pub mod file_sender;
+
+use std::env;
+use std::io;
+
+use dotenvy::dotenv;
+use mongodb::bson::DateTime;
+use rocket::async_trait;
+use thiserror::Error;
+
+use self::file_sender::FileSender;
+
+#[derive(Debug, Error)]
+pub enum NotificationError {
+    #[error("notification transport error: {0}")]
+    Transport(#[from] io::Error),
+}
+
+/// Delivers the messages a user needs outside the API, such as password reset
+/// tokens. `FileSender` is meant for local use; a mail or SMS sender plugs in
+/// behind the same trait.
+#[async_trait]
+pub trait NotificationSender: Send + Sync {
+    async fn send_password_reset(
+        &self,
+        email: &str,
+        token: &str,
+        expires_at: DateTime,
+    ) -> Result<(), NotificationError>;
+}
+
+/// Picks the sender named by `NOTIFICATION_SENDER`. It defaults to `file`, which
+/// appends to `NOTIFICATION_FILE`; `log` prints the notifications, live reset tokens
+/// included, and must be asked for explicitly, for local runs only.
+pub fn from_env() -> Result<Box<dyn NotificationSender>, String> {
+    dotenv().ok();
+    let kind = env::var("NOTIFICATION_SENDER").unwrap_or_else(|_| "file".to_string());
+    match kind.as_str() {
+        "file" => {
+            let path = env::var("NOTIFICATION_FILE").map_err(|_| {
+                "NOTIFICATION_FILE must be set when NOTIFICATION_SENDER is file".to_string()
+            })?;
+            Ok(Box::new(FileSender::new(Some(path.into()))))
+        }
+        "log" => {
+            println!("NOTIFICATION_SENDER is log, password reset tokens are printed");
+            Ok(Box::new(FileSender::new(None)))
+        }
+        other => Err(format!(
+            "NOTIFICATION_SENDER must be file or log, got {}",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn printing_tokens_must_be_asked_for() {
+        env::remove_var("NOTIFICATION_SENDER");
+        env::remove_var("NOTIFICATION_FILE");
+        assert!(from_env().is_err());
+
+        env::set_var("NOTIFICATION_FILE", "notifications.log");
+        assert!(from_env().is_ok());
+
+        env::set_var("NOTIFICATION_SENDER", "smtp");
+        assert!(from_env().is_err());
+
+        env::set_var("NOTIFICATION_SENDER", "log");
+        env::remove_var("NOTIFICATION_FILE");
+        assert!(from_env().is_ok());
+        env::remove_var("NOTIFICATION_SENDER");
+    }
+}
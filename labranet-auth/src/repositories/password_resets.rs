@@ -0,0 +1,54 @@
+use labranet_common::repository::{retry, RepoResult};
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use rocket::async_trait;
+
+use crate::{db::db::MongoDB, entities::password_resets::PasswordReset};
+
+#[async_trait]
+pub trait PasswordResetRepoTrait: Send + Sync {
+    async fn add(&self, reset: PasswordReset) -> RepoResult<()>;
+    async fn consume(&self, token_hash: String) -> RepoResult<Option<PasswordReset>>;
+    async fn invalidate_user(&self, user_id: ObjectId) -> RepoResult<()>;
+}
+pub struct PasswordResetRepo {
+    mongo: MongoDB,
+}
+impl PasswordResetRepo {
+    pub fn new(mongo: MongoDB) -> Self {
+        PasswordResetRepo { mongo }
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepoTrait for PasswordResetRepo {
+    async fn add(&self, reset: PasswordReset) -> RepoResult<()> {
+        let col = self.mongo.database.collection::<PasswordReset>("password_resets");
+        col.insert_one(&reset).await?;
+        Ok(())
+    }
+    /// Marks the token as used and returns it, or `None` when it is unknown, used
+    /// or expired. Not retried, so the same token cannot be consumed twice.
+    async fn consume(&self, token_hash: String) -> RepoResult<Option<PasswordReset>> {
+        let col = self.mongo.database.collection::<PasswordReset>("password_resets");
+        let now = DateTime::now();
+        Ok(col
+            .find_one_and_update(
+                doc! {"token_hash":token_hash,"used_at":null,"expires_at":{"$gt":now}},
+                doc! {"$set":{"used_at":now}},
+            )
+            .await?)
+    }
+    /// Spends every token of the user that is still unused.
+    async fn invalidate_user(&self, user_id: ObjectId) -> RepoResult<()> {
+        let col = self.mongo.database.collection::<PasswordReset>("password_resets");
+        let now = DateTime::now();
+        retry(|| {
+            col.update_many(
+                doc! {"user_id":user_id,"used_at":null},
+                doc! {"$set":{"used_at":now}},
+            )
+        })
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use labranet_common::repository::{retry, RepoResult};
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use rocket::async_trait;
+
+use crate::{db::db::MongoDB, entities::refresh_tokens::RefreshToken};
+
+#[async_trait]
+pub trait RefreshTokenRepoTrait: Send + Sync {
+    async fn add(&self, token: RefreshToken) -> RepoResult<()>;
+    async fn find_by_hash(&self, token_hash: String) -> RepoResult<Option<RefreshToken>>;
+    /// `false` when the token was no longer live, i.e. another request replaced or
+    /// revoked it first.
+    async fn replace(&self, _id: ObjectId, replaced_by: ObjectId) -> RepoResult<bool>;
+    async fn revoke_family(&self, family_id: ObjectId) -> RepoResult<()>;
+    async fn revoke_user(&self, user_id: ObjectId) -> RepoResult<()>;
+    async fn find_live_families(&self, user_id: ObjectId) -> RepoResult<Vec<ObjectId>>;
+}
+pub struct RefreshTokenRepo {
+    mongo: MongoDB,
+}
+impl RefreshTokenRepo {
+    pub fn new(mongo: MongoDB) -> Self {
+        RefreshTokenRepo { mongo }
+    }
+}
+
+#[async_trait]
+impl RefreshTokenRepoTrait for RefreshTokenRepo {
+    async fn add(&self, token: RefreshToken) -> RepoResult<()> {
+        let col = self.mongo.database.collection::<RefreshToken>("refresh_tokens");
+        col.insert_one(&token).await?;
+        Ok(())
+    }
+    async fn find_by_hash(&self, token_hash: String) -> RepoResult<Option<RefreshToken>> {
+        let col = self.mongo.database.collection::<RefreshToken>("refresh_tokens");
+        retry(|| col.find_one(doc! {"token_hash":token_hash.clone()})).await
+    }
+    async fn replace(&self, _id: ObjectId, replaced_by: ObjectId) -> RepoResult<bool> {
+        let col = self.mongo.database.collection::<RefreshToken>("refresh_tokens");
+        // Only a live token can be replaced, so two requests racing with the same
+        // token cannot both succeed. Not retried for the same reason.
+        let result = col
+            .update_one(
+                doc! {"_id":_id,"replaced_by":null,"revoked":false},
+                doc! {"$set":{"replaced_by":replaced_by}},
+            )
+            .await?;
+        Ok(result.modified_count > 0)
+    }
+    async fn revoke_family(&self, family_id: ObjectId) -> RepoResult<()> {
+        let col = self.mongo.database.collection::<RefreshToken>("refresh_tokens");
+        retry(|| {
+            col.update_many(
+                doc! {"family_id":family_id},
+                doc! {"$set":{"revoked":true}},
+            )
+        })
+        .await?;
+        Ok(())
+    }
+    async fn revoke_user(&self, user_id: ObjectId) -> RepoResult<()> {
+        let col = self.mongo.database.collection::<RefreshToken>("refresh_tokens");
+        retry(|| col.update_many(doc! {"user_id":user_id}, doc! {"$set":{"revoked":true}})).await?;
+        Ok(())
+    }
+    /// The families of the user that still have a usable token, i.e. its open sessions.
+    async fn find_live_families(&self, user_id: ObjectId) -> RepoResult<Vec<ObjectId>> {
+        let col = self.mongo.database.collection::<RefreshToken>("refresh_tokens");
+        let filter = doc! {
+            "user_id":user_id,
+            "revoked":false,
+            "replaced_by":null,
+            "expires_at":{"$gt":DateTime::now()}
+        };
+        let families = retry(|| col.distinct("family_id", filter.clone())).await?;
+        Ok(families
+            .into_iter()
+            .filter_map(|family_id| family_id.as_object_id())
+            .collect())
+    }
+}
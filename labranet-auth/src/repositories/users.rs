@@ -1,18 +1,32 @@
+use labranet_common::pagination::{Page, Pagination};
+use labranet_common::repository::{retry, RepoResult};
 use mongodb::bson::{doc, oid::ObjectId};
-use mongodb::results::{DeleteResult, InsertOneResult, UpdateResult};
+use mongodb::results::{DeleteResult, InsertOneResult};
 use rocket::async_trait;
-use rocket::futures::StreamExt;
-use crate::{db::db::MongoDB, entities::users::User};
+use rocket::futures::TryStreamExt;
+use crate::{db::db::MongoDB, entities::users::User, models::users::UserFilterModel};
 
 #[async_trait]
 pub trait  UserRepoTrait : Send+Sync {
-    async fn add(&self,user:User)->InsertOneResult ;
-    async fn find_all(&self)->Vec<User>;
-    async fn find_one(&self,_id:ObjectId)->Option<User>;
-    async fn update(&self,user:User,_id:ObjectId)->UpdateResult;
-    async fn delete(&self,_id:ObjectId)->DeleteResult;
-    async fn find_by_email(&self,email:String)->Option<User>;
+    async fn add(&self,user:User)->RepoResult<InsertOneResult> ;
+    async fn find_all(&self,filter:UserFilterModel,pagination:&Pagination)->RepoResult<Page<User>>;
+    async fn find_one(&self,_id:ObjectId)->RepoResult<Option<User>>;
+    async fn update(&self,user:User,_id:ObjectId)->RepoResult<()>;
+    async fn delete(&self,_id:ObjectId)->RepoResult<DeleteResult>;
+    async fn find_by_email(&self,email:String)->RepoResult<Option<User>>;
+    async fn count(&self)->RepoResult<u64>;
 }
+/// `value` as a regex that matches it literally.
+fn regex_escape(value: &str) -> String {
+    value
+        .chars()
+        .flat_map(|c| match "\\^$.|?*+()[]{}".contains(c) {
+            true => vec!['\\', c],
+            false => vec![c],
+        })
+        .collect()
+}
+
 pub struct UserRepo  {
     mongo:MongoDB
 }
@@ -27,7 +41,7 @@ impl UserRepoTrait for UserRepo{
 
    
     
-    async fn add(&self,user:User)->InsertOneResult {
+    async fn add(&self,user:User)->RepoResult<InsertOneResult> {
         let col = self.mongo.database.collection::<User>("users");
         let new_user:User=User{
             _id:user._id,
@@ -36,32 +50,52 @@ impl UserRepoTrait for UserRepo{
             password:user.password,
             first_name:user.first_name,
             last_name:user.last_name,
-            role:user.role
+            role:user.role,
+            disabled:user.disabled
         };
         
-        col.insert_one(&new_user).await.unwrap()
+        Ok(col.insert_one(&new_user).await?)
       
     }
-    async fn find_all(&self)->Vec<User> {
+    async fn find_all(&self,filter:UserFilterModel,pagination:&Pagination)->RepoResult<Page<User>> {
         let col = self.mongo.database.collection::<User>("users");
-        let mut  cursor = col.find(doc! {}).await.unwrap();
-        let mut results :Vec<User>=Vec::new();
-        while let Some(result) = cursor.next().await {
-           if result.is_ok(){
-            results.push(result.unwrap());
-           }
-            
+        let mut query = doc! {};
+        if let Some(email) = filter.email {
+            query.insert("email", email);
+        }
+        if let Some(role) = filter.role {
+            query.insert("role", role);
+        }
+        if let Some(disabled) = filter.disabled {
+            query.insert("disabled", disabled);
         }
-        results
+        if let Some(search) = filter.search {
+            let pattern = doc! {"$regex":regex_escape(&search),"$options":"i"};
+            query.insert("$or", vec![
+                doc! {"email":pattern.clone()},
+                doc! {"first_name":pattern.clone()},
+                doc! {"last_name":pattern},
+            ]);
+        }
+        let total = retry(|| col.count_documents(query.clone())).await?;
+        let results = retry(|| async {
+            col.find(query.clone())
+                .sort(doc! {pagination.sort_field.as_str():pagination.sort_order})
+                .skip(pagination.skip())
+                .limit(pagination.limit as i64)
+                .await?
+                .try_collect::<Vec<User>>()
+                .await
+        }).await?;
+        Ok(Page::new(results, total, pagination))
     }
-    async fn find_one(&self,_id:ObjectId)->Option<User> {
+    async fn find_one(&self,_id:ObjectId)->RepoResult<Option<User>> {
         let col = self.mongo.database.collection::<User>("users");
-        let result = col.find_one(doc! {"_id":_id}).await.unwrap();
-        result
+        retry(|| col.find_one(doc! {"_id":_id})).await
     }
-    async fn update(&self,user:User,_id:ObjectId)->UpdateResult {
+    async fn update(&self,user:User,_id:ObjectId)->RepoResult<()> {
         let col = self.mongo.database.collection::<User>("users");
-        let result = col.update_one(doc! {"_id":_id}, doc!{"$set":doc!{
+        let update = doc!{"$set":doc!{
             "_id":user._id,
             "email":user.email,
             "mobile":user.mobile,
@@ -69,17 +103,21 @@ impl UserRepoTrait for UserRepo{
             "first_name":user.first_name,
             "last_name":user.last_name,
             "role":user.role,
-        }}).await.unwrap();
-        result
+            "disabled":user.disabled,
+        }};
+        retry(|| col.update_one(doc! {"_id":_id}, update.clone())).await?;
+        Ok(())
+    }
+    async fn delete(&self,_id:ObjectId)->RepoResult<DeleteResult> {
+        let col = self.mongo.database.collection::<User>("users");
+        retry(|| col.delete_one(doc! {"_id":_id})).await
     }
-    async fn delete(&self,_id:ObjectId)->DeleteResult {
+    async fn find_by_email(&self,email:String)->RepoResult<Option<User>> {
         let col = self.mongo.database.collection::<User>("users");
-        let result = col.delete_one(doc! {"_id":_id}).await.unwrap();
-        result
+        retry(|| col.find_one(doc! {"email":email.clone()})).await
     }
-    async fn find_by_email(&self,email:String)->Option<User> {
+    async fn count(&self)->RepoResult<u64> {
         let col = self.mongo.database.collection::<User>("users");
-        let result = col.find_one(doc! {"email":email}).await.unwrap();
-        result
+        retry(|| col.count_documents(doc! {})).await
     }
 }
\ No newline at end of file
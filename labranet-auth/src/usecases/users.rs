@@ -1,233 +1,1022 @@
+use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use chrono::Utc;
+use dotenvy::dotenv;
 use jsonwebtoken::jwk::Jwk;
-use labranet_common::jwt::{create_jwt, decode_jwt, JWT};
-use labranet_common::response::{Response, ResponseBody, ResponseError, ResponseErrorBody};
+use labranet_common::jwt::{Authorized, Claims, JWT};
+use labranet_common::permissions::UsersAdmin;
+use labranet_common::error::AppError;
+use labranet_common::events::auth::{
+    SessionRevokedEvent, SessionsRevokedEvent, TokenRevokedEvent, UserChangedEvent,
+};
+use labranet_common::events::{Event, Publisher};
+use labranet_common::pagination::{Page, PageQuery};
+use labranet_common::response::{Response, ResponseBody, ResponseError};
+use labranet_common::revocations::Denylist;
+use labranet_common::roles::Role;
+use labranet_common::validation::{field_error, validate, validate_with, ValidationErrors};
 
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use rocket::async_trait;
 
-use rocket::http::Status;
-use rocket::response::status::{Created, Custom};
-use labranet_common::roles::Role;
+use rocket::response::status::Created;
+use crate::entities::password_resets::PasswordReset;
+use crate::entities::refresh_tokens::RefreshToken;
 use crate::entities::users::User;
+use crate::views::users::UserView;
 use crate::models::login::LoginModel;
-use crate::models::users::UserModel;
+use crate::models::tokens::{RefreshModel, TokenModel};
+use crate::models::users::{
+    ForgotPasswordModel, PasswordModel, ProfileModel, ResetPasswordModel, RoleModel, SignUpModel,
+    UserFilterModel, UserModel,
+};
+use crate::notifications::NotificationSender;
 
+use crate::repositories::password_resets::PasswordResetRepoTrait;
+use crate::repositories::refresh_tokens::RefreshTokenRepoTrait;
 use crate::repositories::users::UserRepoTrait;
+use crate::utils::keys::SigningKeys;
 use crate::utils::password;
+use crate::utils::tokens::{self, TokenConfig};
+
+const USER_SORT_FIELDS: [&str; 4] = ["email", "first_name", "last_name", "role"];
 
 #[async_trait]
 pub trait UserUseCaseTrait : Send+Sync {
-    async fn sign_up(&self, model: UserModel) -> Result<Created<String>, Custom<String>>;
-    async fn login(&self,model:LoginModel)->Result<String,Custom<String>>;
-    async fn get_current_user(&self,key:Result<JWT,ResponseError<String>>)->Result<String,Custom<String>>;
+    async fn sign_up(&self, model: SignUpModel) -> Result<Created<Response<UserView>>, AppError>;
+    async fn create_user(&self, key: Result<Authorized<UsersAdmin>, AppError>, model: UserModel) -> Result<Created<Response<UserView>>, AppError>;
+    async fn seed_admin(&self, seed: Option<AdminSeed>) -> Result<(), AppError>;
+    async fn login(&self,model:LoginModel)->Result<Response<TokenModel>,AppError>;
+    async fn refresh(&self,model:RefreshModel)->Result<Response<TokenModel>,AppError>;
+    async fn logout(&self,key:Result<JWT,ResponseError<String>>)->Result<Response<String>,AppError>;
+    async fn revoke_sessions(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str)->Result<Response<String>,AppError>;
+    async fn fetch_all_user(&self,key:Result<Authorized<UsersAdmin>,AppError>,filter:UserFilterModel,query:PageQuery)->Result<Response<Page<UserView>>,AppError>;
+    async fn fetch_one_user(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str)->Result<Response<UserView>,AppError>;
+    async fn change_role(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str,model:RoleModel)->Result<Response<UserView>,AppError>;
+    async fn set_disabled(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str,disabled:bool)->Result<Response<UserView>,AppError>;
+    async fn delete_user(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str)->Result<Response<String>,AppError>;
+    async fn get_current_user(&self,key:Result<JWT,ResponseError<String>>)->Result<Response<UserView>,AppError>;
+    async fn update_profile(&self,key:Result<JWT,ResponseError<String>>,model:ProfileModel)->Result<Response<UserView>,AppError>;
+    async fn change_password(&self,key:Result<JWT,ResponseError<String>>,model:PasswordModel)->Result<Response<String>,AppError>;
+    async fn forgot_password(&self,model:ForgotPasswordModel)->Result<Response<String>,AppError>;
+    async fn reset_password(&self,model:ResetPasswordModel)->Result<Response<String>,AppError>;
+}
+/// The admin created when the service starts with no users at all, read from
+/// `ADMIN_EMAIL`, `ADMIN_PASSWORD` and `ADMIN_MOBILE`.
+#[derive(Debug, Clone)]
+pub struct AdminSeed {
+    pub email: String,
+    pub password: String,
+    pub mobile: String,
+}
+impl AdminSeed {
+    /// `None` unless `ADMIN_EMAIL` is set; the other two are then required.
+    pub fn from_env() -> Option<Self> {
+        dotenv().ok();
+        let email = env::var("ADMIN_EMAIL").ok()?;
+        Some(AdminSeed {
+            email,
+            password: env::var("ADMIN_PASSWORD").expect("ADMIN_PASSWORD must be set with ADMIN_EMAIL."),
+            mobile: env::var("ADMIN_MOBILE").expect("ADMIN_MOBILE must be set with ADMIN_EMAIL."),
+        })
+    }
 }
+
 pub struct UserUseCase {
     repo: Box<dyn UserRepoTrait>,
+    token_repo: Box<dyn RefreshTokenRepoTrait>,
+    reset_repo: Box<dyn PasswordResetRepoTrait>,
+    notifier: Box<dyn NotificationSender>,
+    token_config: TokenConfig,
+    signing_keys: Arc<SigningKeys>,
+    publisher: Publisher,
+    denylist: Arc<Denylist>,
 }
 impl UserUseCase{
-    pub fn new(repo: Box<dyn UserRepoTrait>) -> Self {
-        UserUseCase{repo}
-    }
-    fn map_field(&self, field: String, message: String) -> Option<String> {
-        match field == "" {
-            true => Some(message.to_string()),
-            false => None,
-        }
-    }
-    fn validate_sign_up(&self, model: UserModel) -> Vec<String> {
-        let errors = [
-            self.map_field(model.email, "Email is required".to_string()),
-            self.map_field(model.mobile, "Mobile is required".to_string()),
-            self.map_field(model.password, "Password is required".to_string()),
-            self.map_field(model.first_name, "First Name is required".to_string()),
-            self.map_field(model.last_name, "Last Name is required".to_string()),
-            match model.role {
-                Role::Admin | Role::Worker=>None,
-                _ => Some("Role is required".to_string()),
-            },
-        ]
-        .to_vec();
-        errors
-            .iter()
-            .map(|x| x.to_owned().to_owned())
-            .flatten()
-            .collect::<Vec<String>>()
-    }
-
-    fn validate_log_in(&self,model:LoginModel)->Vec<String> {
-        let errors = [
-            self.map_field(model.email, "Email is required".to_string()),
-            self.map_field(model.password, "Password is required".to_string()),
-        ]
-        .to_vec();
-        errors
-            .iter()
-            .map(|x| x.to_owned().to_owned())
-            .flatten()
-            .collect::<Vec<String>>()
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(repo: Box<dyn UserRepoTrait>,token_repo: Box<dyn RefreshTokenRepoTrait>,reset_repo: Box<dyn PasswordResetRepoTrait>,notifier: Box<dyn NotificationSender>,token_config: TokenConfig,signing_keys: Arc<SigningKeys>,publisher: Publisher,denylist: Arc<Denylist>) -> Self {
+        UserUseCase{repo,token_repo,reset_repo,notifier,token_config,signing_keys,publisher,denylist}
+    }
+    async fn publish<E: Event>(&self, event: E) {
+        if let Err(err) = self.publisher.publish(&event).await {
+            println!("Cannot publish {}: {}", E::SUBJECT, err);
+        }
+    }
+
+    /// Signs an access token for `user` and stores a new refresh token `token_id` in `family_id`.
+    async fn issue_tokens(&self,user:&User,family_id:ObjectId,token_id:ObjectId)->Result<TokenModel,AppError>{
+        let claims = Claims::new(user._id.to_string(), user.email.clone(),user.role.clone(),family_id.to_hex(),self.token_config.access_ttl);
+        let access_token = self.signing_keys.create_jwt(&claims)
+            .map_err(|err| AppError::Internal(format!("Cannot sign token: {}", err)))?;
+        let refresh_token = tokens::generate();
+        let now = DateTime::now();
+        self.token_repo.add(RefreshToken {
+            _id: token_id,
+            family_id,
+            user_id: user._id,
+            token_hash: tokens::hash(&refresh_token),
+            created_at: now,
+            expires_at: DateTime::from_millis(now.timestamp_millis() + self.token_config.refresh_ttl.num_milliseconds()),
+            replaced_by: None,
+            revoked: false,
+        }).await?;
+        Ok(TokenModel {
+            access_token,
+            refresh_token,
+            token_type: "Bearer".to_string(),
+            expires_in: self.token_config.access_ttl.num_seconds(),
+        })
+    }
+
+    async fn add_user(&self, model: UserModel) -> Result<User, AppError> {
+        validate(&model)?;
+        if self.repo.find_by_email(model.email.to_string()).await?.is_some() {
+            return Err(AppError::conflict("User is already exists"));
+        }
+        let user = User {
+            _id:ObjectId::new(),
+            email: model.email,
+            mobile: model.mobile,
+            password: password::hash(model.password),
+            first_name: model.first_name,
+            last_name: model.last_name,
+            role: model.role.to_string(),
+            disabled: false,
+        };
+        let insert_result = self.repo.add(user).await?;
+        self
+            .repo
+            .find_one(insert_result.inserted_id.as_object_id().unwrap())
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not found User".to_string()))
+    }
+
+    async fn find_user(&self, _id: &str) -> Result<User, AppError> {
+        let id = ObjectId::parse_str(_id)?;
+        self.repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not found User".to_string()))
+    }
+
+    /// Admins manage other accounts; locking themselves out is refused.
+    fn check_not_self(&self, key: &Authorized<UsersAdmin>, user: &User) -> Result<(), AppError> {
+        match key.jwt.claims.subject_id == user._id.to_string() {
+            true => Err(AppError::conflict("Admins cannot change their own account")),
+            false => Ok(()),
+        }
+    }
+
+    /// Replaces the user's pending reset tokens with a new one and sends it.
+    async fn start_password_reset(&self, user: &User) -> Result<(), AppError> {
+        self.reset_repo.invalidate_user(user._id).await?;
+        let token = tokens::generate();
+        let now = DateTime::now();
+        let expires_at = DateTime::from_millis(now.timestamp_millis() + self.token_config.reset_ttl.num_milliseconds());
+        self.reset_repo.add(PasswordReset {
+            _id: ObjectId::new(),
+            user_id: user._id,
+            token_hash: tokens::hash(&token),
+            created_at: now,
+            expires_at,
+            used_at: None,
+        }).await?;
+        self.notifier.send_password_reset(&user.email, &token, expires_at).await
+            .map_err(|err| AppError::Unavailable(format!("Cannot send password reset: {}", err)))?;
+        Ok(())
+    }
+
+    /// Revokes every refresh token of the user and every access token issued so far.
+    async fn end_sessions(&self, user_id: ObjectId) -> Result<(), AppError> {
+        self.token_repo.revoke_user(user_id).await?;
+        let revoked_before = Utc::now().timestamp() as usize;
+        self.denylist.revoke_sessions(user_id.to_string(), revoked_before);
+        self.publish(SessionsRevokedEvent { user_id: user_id.to_string(), revoked_before }).await;
+        Ok(())
+    }
+
+    /// Revokes the refresh tokens of the session `family_id` and every access token
+    /// issued for it.
+    async fn end_session(&self, family_id: ObjectId) -> Result<(), AppError> {
+        // No token of a session outlives the access token issued at its last refresh.
+        let exp = (Utc::now() + self.token_config.access_ttl).timestamp() as usize;
+        self.token_repo.revoke_family(family_id).await?;
+        self.denylist.revoke_session(family_id.to_hex(), exp);
+        self.publish(SessionRevokedEvent { sid: family_id.to_hex(), exp }).await;
+        Ok(())
+    }
+
+    /// Ends every open session of the user except `sid`, the one the request came from.
+    async fn end_other_sessions(&self, user_id: ObjectId, sid: &str) -> Result<(), AppError> {
+        for family_id in self.token_repo.find_live_families(user_id).await? {
+            if family_id.to_hex() != sid {
+                self.end_session(family_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores a changed user, ends its sessions so no token carries the old role or
+    /// status, and announces the change.
+    async fn save_user(&self, user: User) -> Result<User, AppError> {
+        self.repo.update(user.clone(), user._id).await?;
+        self.end_sessions(user._id).await?;
+        let result = self
+            .repo
+            .find_one(user._id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not found User".to_string()))?;
+        self.publish(self.changed_event(&result, false)).await;
+        Ok(result)
+    }
+
+    fn changed_event(&self, user: &User, deleted: bool) -> UserChangedEvent {
+        UserChangedEvent {
+            id: user._id.to_hex(),
+            email: user.email.clone(),
+            role: user.role.clone(),
+            disabled: user.disabled,
+            deleted,
+        }
+    }
+
+    /// A refresh token presented after it was replaced was copied by someone else;
+    /// neither copy nor any access token of the session is trusted any more.
+    async fn reject_reuse(&self,token:&RefreshToken)->AppError{
+        println!("Refresh token reuse detected, revoking family {}", token.family_id);
+        match self.end_session(token.family_id).await {
+            Ok(_) => AppError::Unauthorized("Refresh token reuse detected".to_string()),
+            Err(err) => err,
+        }
     }
 }
 
 #[async_trait]
 impl UserUseCaseTrait for UserUseCase {
     
-   async fn get_current_user(&self ,key:Result<JWT,ResponseError<String>>)->Result<String,Custom<String>>{
-     match key {
-         Ok(k)=>{
-            let result = self.repo.find_by_email(k.claims.subject_email).await;
-            match result {
-                Some(user)=>{
-                    let response = Response {
-                        body:ResponseBody::<User>::Data(user)
-                    };
-                    Ok( serde_json::to_string(&response).unwrap())
-                },
-                _ => {
-                    let response = ResponseError{
-                        error:ResponseErrorBody::<String>::Error("Not found User".to_string())
-                    };
-                    Err(Custom(
-                        Status {
-                            code: Status::BadRequest.code,
-                        },
-                        serde_json::to_string(&response).unwrap(),
-                    ))
-                }
-            }
-         },
-         _ => {
-            let response = ResponseError{
-                error:ResponseErrorBody::<String>::Error("Unauthorize".to_string())
-            };
-            Err(Custom(
-                Status {
-                    code: Status::Unauthorized.code,
-                },
-                serde_json::to_string(&response).unwrap(),
-            ))
-         }
-     }
+   async fn get_current_user(&self ,key:Result<JWT,ResponseError<String>>)->Result<Response<UserView>,AppError>{
+        let k = key?;
+        // By id: the email in the token is stale once the user changed it.
+        let user = self.find_user(&k.claims.subject_id).await?;
+        Ok(Response {
+            body:ResponseBody::<UserView>::Data(user.into())
+        })
    }
+
+    async fn update_profile(&self,key:Result<JWT,ResponseError<String>>,model:ProfileModel)->Result<Response<UserView>,AppError> {
+        let k = key?;
+        let mut user = self.find_user(&k.claims.subject_id).await?;
+        let email_changed = model.email.as_ref().is_some_and(|email| *email != user.email);
+        let mut errors = ValidationErrors::new();
+        if email_changed {
+            let email = model.email.clone().unwrap_or_default();
+            if self.repo.find_by_email(email).await?.is_some() {
+                errors.add("email", field_error("unique", "Email is already in use"));
+            }
+        }
+        validate_with(&model, errors)?;
+        user.email = model.email.unwrap_or(user.email);
+        user.mobile = model.mobile.unwrap_or(user.mobile);
+        user.first_name = model.first_name.unwrap_or(user.first_name);
+        user.last_name = model.last_name.unwrap_or(user.last_name);
+        self.repo.update(user.clone(), user._id).await?;
+        let result = self.find_user(&k.claims.subject_id).await?;
+        if email_changed {
+            self.publish(self.changed_event(&result, false)).await;
+        }
+        Ok(Response {
+            body: ResponseBody::<UserView>::Data(result.into()),
+        })
+    }
+
+    async fn change_password(&self,key:Result<JWT,ResponseError<String>>,model:PasswordModel)->Result<Response<String>,AppError> {
+        let claims = key?.claims;
+        let mut user = self.find_user(&claims.subject_id).await?;
+        let mut errors = ValidationErrors::new();
+        if !password::verify(model.current_password.to_string(), user.password.clone()) {
+            errors.add("current_password", field_error("password", "Current password is incorrect"));
+        }
+        validate_with(&model, errors)?;
+        user.password = password::hash(model.new_password);
+        self.repo.update(user.clone(), user._id).await?;
+        self.end_other_sessions(user._id, &claims.sid).await?;
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Password Changed".to_string()),
+        })
+    }
     
-    async fn sign_up(&self, model: UserModel) -> Result<Created<String>, Custom<String>> {
-        let errors = self.validate_sign_up(model.clone());
-        match errors.len() > 0 {
-            true => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::BadRequest.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            },
-            false => {
-                let exist_user = self.repo.find_by_email(model.email.to_string()).await;
-                match exist_user {
-                    Some(_) => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(
-                                "User is already exists".to_string(),
-                            ),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    None => {
-                        
-                        let user = User {
-                            _id:ObjectId::new(),
-                            email: model.email,
-                            mobile: model.mobile,
-                            password: password::hash(model.password),
-                            first_name: model.first_name,
-                            last_name: model.last_name,
-                            role: model.role.to_string(),
-                        };
-                        let insert_result = self.repo.add(user).await;
-                        let result = self
-                            .repo
-                            .find_one(insert_result.inserted_id.as_object_id().unwrap())
-                            .await
-                            .unwrap();
-                        let response = Response {
-                            body: ResponseBody::<User>::Data(result),
-                        };
-                        Ok(Created::new("").tagged_body(serde_json::to_string(&response).unwrap()))
-                    }
-                }
+    async fn sign_up(&self, model: SignUpModel) -> Result<Created<Response<UserView>>, AppError> {
+        let result = self.add_user(model.into_user(Role::Worker)).await?;
+        let response = Response {
+            body: ResponseBody::<UserView>::Data(result.into()),
+        };
+        Ok(Created::new("").body(response))
+    }
+
+    async fn create_user(&self, key: Result<Authorized<UsersAdmin>, AppError>, model: UserModel) -> Result<Created<Response<UserView>>, AppError> {
+        key?;
+        let result = self.add_user(model).await?;
+        let response = Response {
+            body: ResponseBody::<UserView>::Data(result.into()),
+        };
+        Ok(Created::new("").body(response))
+    }
+
+    async fn seed_admin(&self, seed: Option<AdminSeed>) -> Result<(), AppError> {
+        if self.repo.count().await? > 0 {
+            return Ok(());
+        }
+        match seed {
+            None => println!("No users yet and ADMIN_EMAIL not set, no admin was created"),
+            Some(seed) => {
+                let admin = self.add_user(UserModel {
+                    email: seed.email,
+                    mobile: seed.mobile,
+                    password: seed.password,
+                    first_name: "Admin".to_string(),
+                    last_name: "Admin".to_string(),
+                    role: Role::Admin,
+                }).await?;
+                println!("Created admin {}", admin.email);
             }
         }
+        Ok(())
     }
     
-    async fn login(&self,model:LoginModel)->Result<String,Custom<String>> {
-        let errors =self.validate_log_in(model.clone());
-        match errors.len()>0 {
-            true=>{
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::BadRequest.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            },
-            false=>{
-                let exist_user = self.repo.find_by_email(model.email.to_string()).await;
-                match exist_user {
-                    Some(user)=>{
-                        match password::verify(model.password.to_string(),user.password) {
-                            true=>{
-                                match create_jwt(user._id.to_string(), user.email,user.role){
-                                    Ok(jwt)=>{
-                                        let response = Response {
-                                            body: ResponseBody::<String>::Data(jwt.to_string()),
-                                        };
-                                        Ok(serde_json::to_string(&response).unwrap())
-                                    },
-                                    _ =>{
-                                        let response = ResponseError {
-                                            error: ResponseErrorBody::<String>::Error("Login Fail".to_string()),
-                                        };
-                                        Err(Custom(
-                                            Status { code: 401 },
-                                            serde_json::to_string(&response).unwrap(),
-                                        ))
-                                      }
-                                }
-
-                            },
-                            false=>{
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error("Login Fail".to_string()),
-                                };
-                                Err(Custom(
-                                    Status { code: 401 },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            }
-                        }
-                    },
-                    _ => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error("Login Fail".to_string()),
-                        };
-                        Err(Custom(
-                            Status { code: 401 },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    
+    async fn login(&self,model:LoginModel)->Result<Response<TokenModel>,AppError> {
+        validate(&model)?;
+        let user = match self.repo.find_by_email(model.email.to_string()).await? {
+            Some(user) if password::verify(model.password.to_string(),user.password.clone()) => user,
+            _ => return Err(AppError::Unauthorized("Login Fail".to_string())),
+        };
+        if user.disabled {
+            return Err(AppError::Forbidden("Account is disabled".to_string()));
+        }
+        let tokens = self.issue_tokens(&user, ObjectId::new(), ObjectId::new()).await?;
+        Ok(Response {
+            body: ResponseBody::<TokenModel>::Data(tokens),
+        })
+    }
+
+    async fn refresh(&self,model:RefreshModel)->Result<Response<TokenModel>,AppError> {
+        validate(&model)?;
+        let invalid = || AppError::Unauthorized("Invalid refresh token".to_string());
+        let token = self
+            .token_repo
+            .find_by_hash(tokens::hash(&model.refresh_token))
+            .await?
+            .ok_or_else(invalid)?;
+        if token.revoked || token.replaced_by.is_some() {
+            return Err(self.reject_reuse(&token).await);
+        }
+        if token.expires_at < DateTime::now() {
+            return Err(AppError::Unauthorized("Refresh token expired".to_string()));
+        }
+        let user = self.repo.find_one(token.user_id).await?.ok_or_else(invalid)?;
+        if user.disabled {
+            return Err(AppError::Forbidden("Account is disabled".to_string()));
+        }
+        let replacement_id = ObjectId::new();
+        // Another request used the same token first.
+        if !self.token_repo.replace(token._id, replacement_id).await? {
+            return Err(self.reject_reuse(&token).await);
+        }
+        let tokens = self.issue_tokens(&user, token.family_id, replacement_id).await?;
+        Ok(Response {
+            body: ResponseBody::<TokenModel>::Data(tokens),
+        })
+    }
+
+    async fn logout(&self,key:Result<JWT,ResponseError<String>>)->Result<Response<String>,AppError> {
+        let claims = key?.claims;
+        self.token_repo.revoke_family(ObjectId::parse_str(&claims.sid)?).await?;
+        // This instance hears its own event too, but must not accept the token in the meantime.
+        self.denylist.revoke_token(claims.jti.clone(), claims.exp);
+        self.publish(TokenRevokedEvent { jti: claims.jti, exp: claims.exp }).await;
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Logout Success".to_string()),
+        })
+    }
+
+    async fn revoke_sessions(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str)->Result<Response<String>,AppError> {
+        key?;
+        let user = self.find_user(_id).await?;
+        self.end_sessions(user._id).await?;
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Sessions Revoked".to_string()),
+        })
+    }
+
+    async fn fetch_all_user(&self,key:Result<Authorized<UsersAdmin>,AppError>,filter:UserFilterModel,query:PageQuery)->Result<Response<Page<UserView>>,AppError> {
+        key?;
+        if let Some(role) = &filter.role {
+            Role::from_str(role).map_err(AppError::Validation)?;
+        }
+        let pagination = query.validate(&USER_SORT_FIELDS)?;
+        let results = self.repo.find_all(filter, &pagination).await?;
+        Ok(Response {
+            body: ResponseBody::<Page<UserView>>::Data(results.map(UserView::from)),
+        })
+    }
+
+    async fn fetch_one_user(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str)->Result<Response<UserView>,AppError> {
+        key?;
+        let user = self.find_user(_id).await?;
+        Ok(Response {
+            body: ResponseBody::<UserView>::Data(user.into()),
+        })
+    }
+
+    async fn change_role(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str,model:RoleModel)->Result<Response<UserView>,AppError> {
+        let key = key?;
+        let mut user = self.find_user(_id).await?;
+        self.check_not_self(&key, &user)?;
+        user.role = model.role.to_string();
+        let result = self.save_user(user).await?;
+        Ok(Response {
+            body: ResponseBody::<UserView>::Data(result.into()),
+        })
+    }
+
+    async fn set_disabled(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str,disabled:bool)->Result<Response<UserView>,AppError> {
+        let key = key?;
+        let mut user = self.find_user(_id).await?;
+        self.check_not_self(&key, &user)?;
+        user.disabled = disabled;
+        let result = self.save_user(user).await?;
+        Ok(Response {
+            body: ResponseBody::<UserView>::Data(result.into()),
+        })
+    }
+
+    async fn delete_user(&self,key:Result<Authorized<UsersAdmin>,AppError>,_id:&str)->Result<Response<String>,AppError> {
+        let key = key?;
+        let user = self.find_user(_id).await?;
+        self.check_not_self(&key, &user)?;
+        self.repo.delete(user._id).await?;
+        self.end_sessions(user._id).await?;
+        self.publish(self.changed_event(&user, true)).await;
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Delete Success".to_string()),
+        })
+    }
+
+    async fn forgot_password(&self,model:ForgotPasswordModel)->Result<Response<String>,AppError> {
+        validate(&model)?;
+        // The answer is the same whether or not the account exists, so the
+        // endpoint cannot be used to find out which emails are registered. That is
+        // also why a failure to send the reset is only logged.
+        if let Some(user) = self.repo.find_by_email(model.email).await? {
+            if !user.disabled {
+                if let Err(err) = self.start_password_reset(&user).await {
+                    println!("Cannot send password reset to user {}: {}", user._id, err);
                 }
             }
         }
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Password reset sent if the account exists".to_string()),
+        })
+    }
+
+    async fn reset_password(&self,model:ResetPasswordModel)->Result<Response<String>,AppError> {
+        // Checked before the token is consumed so a rejected password does not spend it.
+        validate(&model)?;
+        let Some(reset) = self.reset_repo.consume(tokens::hash(&model.token)).await? else {
+            let mut errors = ValidationErrors::new();
+            errors.add("token", field_error("token", "Reset token is invalid or has expired"));
+            return Err(AppError::InvalidFields(errors));
+        };
+        let mut user = self.find_user(&reset.user_id.to_hex()).await?;
+        user.password = password::hash(model.new_password);
+        self.repo.update(user.clone(), user._id).await?;
+        self.reset_repo.invalidate_user(user._id).await?;
+        self.end_sessions(user._id).await?;
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Password Reset".to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use labranet_common::events::memory_bus::MemoryBus;
+    use labranet_common::pagination::Pagination;
+    use labranet_common::repository::RepoResult;
+    use mongodb::results::{DeleteResult, InsertOneResult};
+
+    use super::*;
+    use crate::models::users::UserFilterModel;
+    use crate::notifications::NotificationError;
+
+    #[derive(Clone, Default)]
+    struct FakeUserRepo {
+        users: Arc<Mutex<Vec<User>>>,
+    }
+    #[async_trait]
+    impl UserRepoTrait for FakeUserRepo {
+        async fn add(&self, _user: User) -> RepoResult<InsertOneResult> {
+            unimplemented!("not used by these tests")
+        }
+        async fn find_all(
+            &self,
+            _filter: UserFilterModel,
+            _pagination: &Pagination,
+        ) -> RepoResult<Page<User>> {
+            unimplemented!("not used by these tests")
+        }
+        async fn find_one(&self, _id: ObjectId) -> RepoResult<Option<User>> {
+            Ok(self
+                .users
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|user| user._id == _id)
+                .cloned())
+        }
+        async fn update(&self, user: User, _id: ObjectId) -> RepoResult<()> {
+            let mut users = self.users.lock().unwrap();
+            if let Some(stored) = users.iter_mut().find(|stored| stored._id == _id) {
+                *stored = user;
+            }
+            Ok(())
+        }
+        async fn delete(&self, _id: ObjectId) -> RepoResult<DeleteResult> {
+            unimplemented!("not used by these tests")
+        }
+        async fn find_by_email(&self, email: String) -> RepoResult<Option<User>> {
+            Ok(self
+                .users
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|user| user.email == email)
+                .cloned())
+        }
+        async fn count(&self) -> RepoResult<u64> {
+            Ok(self.users.lock().unwrap().len() as u64)
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct FakeRefreshTokenRepo {
+        tokens: Arc<Mutex<Vec<RefreshToken>>>,
+    }
+    #[async_trait]
+    impl RefreshTokenRepoTrait for FakeRefreshTokenRepo {
+        async fn add(&self, token: RefreshToken) -> RepoResult<()> {
+            self.tokens.lock().unwrap().push(token);
+            Ok(())
+        }
+        async fn find_by_hash(&self, token_hash: String) -> RepoResult<Option<RefreshToken>> {
+            Ok(self
+                .tokens
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|token| token.token_hash == token_hash)
+                .cloned())
+        }
+        async fn replace(&self, _id: ObjectId, replaced_by: ObjectId) -> RepoResult<bool> {
+            let mut tokens = self.tokens.lock().unwrap();
+            match tokens
+                .iter_mut()
+                .find(|token| token._id == _id && token.replaced_by.is_none() && !token.revoked)
+            {
+                Some(token) => {
+                    token.replaced_by = Some(replaced_by);
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        }
+        async fn revoke_family(&self, family_id: ObjectId) -> RepoResult<()> {
+            for token in self
+                .tokens
+                .lock()
+                .unwrap()
+                .iter_mut()
+                .filter(|token| token.family_id == family_id)
+            {
+                token.revoked = true;
+            }
+            Ok(())
+        }
+        async fn revoke_user(&self, user_id: ObjectId) -> RepoResult<()> {
+            for token in self
+                .tokens
+                .lock()
+                .unwrap()
+                .iter_mut()
+                .filter(|token| token.user_id == user_id)
+            {
+                token.revoked = true;
+            }
+            Ok(())
+        }
+        async fn find_live_families(&self, user_id: ObjectId) -> RepoResult<Vec<ObjectId>> {
+            let mut families: Vec<ObjectId> = Vec::new();
+            for token in self.tokens.lock().unwrap().iter() {
+                let live =
+                    token.user_id == user_id && !token.revoked && token.replaced_by.is_none();
+                if live && !families.contains(&token.family_id) {
+                    families.push(token.family_id);
+                }
+            }
+            Ok(families)
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct FakePasswordResetRepo {
+        resets: Arc<Mutex<Vec<PasswordReset>>>,
+    }
+    #[async_trait]
+    impl PasswordResetRepoTrait for FakePasswordResetRepo {
+        async fn add(&self, reset: PasswordReset) -> RepoResult<()> {
+            self.resets.lock().unwrap().push(reset);
+            Ok(())
+        }
+        async fn consume(&self, token_hash: String) -> RepoResult<Option<PasswordReset>> {
+            let now = DateTime::now();
+            let mut resets = self.resets.lock().unwrap();
+            let reset = resets.iter_mut().find(|reset| {
+                reset.token_hash == token_hash && reset.used_at.is_none() && reset.expires_at > now
+            });
+            Ok(reset.map(|reset| {
+                reset.used_at = Some(now);
+                reset.clone()
+            }))
+        }
+        async fn invalidate_user(&self, user_id: ObjectId) -> RepoResult<()> {
+            let now = DateTime::now();
+            for reset in self.resets.lock().unwrap().iter_mut() {
+                if reset.user_id == user_id && reset.used_at.is_none() {
+                    reset.used_at = Some(now);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Keeps the tokens it was asked to send, or fails like an unreachable mail server.
+    #[derive(Clone, Default)]
+    struct FakeNotifier {
+        sent: Arc<Mutex<Vec<(String, String)>>>,
+        down: bool,
+    }
+    #[async_trait]
+    impl NotificationSender for FakeNotifier {
+        async fn send_password_reset(
+            &self,
+            email: &str,
+            token: &str,
+            _expires_at: DateTime,
+        ) -> Result<(), NotificationError> {
+            if self.down {
+                return Err(NotificationError::Transport(std::io::Error::other(
+                    "unreachable",
+                )));
+            }
+            self.sent
+                .lock()
+                .unwrap()
+                .push((email.to_string(), token.to_string()));
+            Ok(())
+        }
+    }
+
+    struct Fixture {
+        use_case: UserUseCase,
+        user: User,
+        users: FakeUserRepo,
+        tokens: FakeRefreshTokenRepo,
+        resets: FakePasswordResetRepo,
+        notifier: FakeNotifier,
+        bus: Arc<MemoryBus>,
+        denylist: Arc<Denylist>,
+    }
+
+    fn fixture() -> Fixture {
+        fixture_with(FakeNotifier::default())
+    }
+
+    fn fixture_with(notifier: FakeNotifier) -> Fixture {
+        let user = User {
+            _id: ObjectId::new(),
+            email: "worker@labranet.test".to_string(),
+            mobile: "0812345678".to_string(),
+            password: password::hash("correct horse".to_string()),
+            first_name: "Wendy".to_string(),
+            last_name: "Worker".to_string(),
+            role: Role::Worker.to_string(),
+            disabled: false,
+        };
+        let users = FakeUserRepo::default();
+        users.users.lock().unwrap().push(user.clone());
+        let tokens = FakeRefreshTokenRepo::default();
+        let resets = FakePasswordResetRepo::default();
+        let bus = Arc::new(MemoryBus::new());
+        let denylist = Arc::new(Denylist::new());
+        let use_case = UserUseCase::new(
+            Box::new(users.clone()),
+            Box::new(tokens.clone()),
+            Box::new(resets.clone()),
+            Box::new(notifier.clone()),
+            TokenConfig {
+                access_ttl: chrono::Duration::minutes(15),
+                refresh_ttl: chrono::Duration::days(30),
+                reset_ttl: chrono::Duration::hours(1),
+            },
+            Arc::new(SigningKeys::from_env()),
+            Publisher::new(bus.clone()),
+            denylist.clone(),
+        );
+        Fixture {
+            use_case,
+            user,
+            users,
+            tokens,
+            resets,
+            notifier,
+            bus,
+            denylist,
+        }
+    }
+
+    async fn login(fixture: &Fixture) -> TokenModel {
+        let model = LoginModel {
+            email: fixture.user.email.clone(),
+            password: "correct horse".to_string(),
+        };
+        let ResponseBody::Data(tokens) = fixture.use_case.login(model).await.unwrap().body;
+        tokens
+    }
+
+    async fn refresh(fixture: &Fixture, refresh_token: &str) -> Result<TokenModel, AppError> {
+        let model = RefreshModel {
+            refresh_token: refresh_token.to_string(),
+        };
+        let ResponseBody::Data(tokens) = fixture.use_case.refresh(model).await?.body;
+        Ok(tokens)
+    }
+
+    fn stored(fixture: &Fixture, refresh_token: &str) -> RefreshToken {
+        let hash = tokens::hash(refresh_token);
+        fixture
+            .tokens
+            .tokens
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|token| token.token_hash == hash)
+            .cloned()
+            .unwrap()
+    }
+
+    #[rocket::async_test]
+    async fn refresh_replaces_the_token_within_its_session() {
+        let fixture = fixture();
+        let first = login(&fixture).await;
+
+        let second = refresh(&fixture, &first.refresh_token).await.unwrap();
+        let third = refresh(&fixture, &second.refresh_token).await;
+
+        let (first, second) = (
+            stored(&fixture, &first.refresh_token),
+            stored(&fixture, &second.refresh_token),
+        );
+        assert_eq!(first.replaced_by, Some(second._id));
+        assert_eq!(first.family_id, second.family_id);
+        assert!(third.is_ok());
+    }
+
+    #[rocket::async_test]
+    async fn reusing_a_replaced_token_ends_the_session() {
+        let fixture = fixture();
+        let first = login(&fixture).await;
+        let second = refresh(&fixture, &first.refresh_token).await.unwrap();
+        let family_id = stored(&fixture, &first.refresh_token).family_id;
+
+        let reused = refresh(&fixture, &first.refresh_token).await;
+        let after_reuse = refresh(&fixture, &second.refresh_token).await;
+
+        assert!(matches!(reused, Err(AppError::Unauthorized(_))));
+        assert!(matches!(after_reuse, Err(AppError::Unauthorized(_))));
+        assert!(stored(&fixture, &second.refresh_token).revoked);
+        let session_token = Claims::new(
+            fixture.user._id.to_string(),
+            fixture.user.email.clone(),
+            fixture.user.role.clone(),
+            family_id.to_hex(),
+            chrono::Duration::minutes(15),
+        );
+        assert!(fixture.denylist.is_revoked(&session_token));
+        // The revoked second token counts as reused too.
+        let revoked = fixture.bus.published::<SessionRevokedEvent>();
+        assert_eq!(revoked.len(), 2);
+        assert!(revoked.iter().all(|event| event.sid == family_id.to_hex()));
+    }
+
+    #[rocket::async_test]
+    async fn reuse_leaves_the_other_sessions_alone() {
+        let fixture = fixture();
+        let stolen = login(&fixture).await;
+        let other = login(&fixture).await;
+        refresh(&fixture, &stolen.refresh_token).await.unwrap();
+
+        assert!(refresh(&fixture, &stolen.refresh_token).await.is_err());
+
+        assert!(refresh(&fixture, &other.refresh_token).await.is_ok());
+    }
+
+    #[rocket::async_test]
+    async fn refuses_unknown_and_expired_tokens() {
+        let fixture = fixture();
+        let tokens = login(&fixture).await;
+        let hash = tokens::hash(&tokens.refresh_token);
+        for token in fixture
+            .tokens
+            .tokens
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter(|token| token.token_hash == hash)
+        {
+            token.expires_at = DateTime::from_millis(DateTime::now().timestamp_millis() - 1000);
+        }
+
+        let unknown = refresh(&fixture, "not a token").await;
+        let expired = refresh(&fixture, &tokens.refresh_token).await;
+
+        assert!(matches!(unknown, Err(AppError::Unauthorized(_))));
+        assert!(matches!(expired, Err(AppError::Unauthorized(_))));
+        assert!(!stored(&fixture, &tokens.refresh_token).revoked);
+        assert!(fixture.bus.published::<SessionRevokedEvent>().is_empty());
+    }
+
+    async fn forgot_password(fixture: &Fixture, email: &str) -> Result<Response<String>, AppError> {
+        let model = ForgotPasswordModel {
+            email: email.to_string(),
+        };
+        fixture.use_case.forgot_password(model).await
+    }
+
+    async fn reset_password(
+        fixture: &Fixture,
+        token: &str,
+        new_password: &str,
+    ) -> Result<Response<String>, AppError> {
+        let model = ResetPasswordModel {
+            token: token.to_string(),
+            new_password: new_password.to_string(),
+        };
+        fixture.use_case.reset_password(model).await
+    }
+
+    fn sent_tokens(fixture: &Fixture) -> Vec<String> {
+        fixture
+            .notifier
+            .sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, token)| token.clone())
+            .collect()
+    }
+
+    fn stored_password(fixture: &Fixture) -> String {
+        fixture.users.users.lock().unwrap()[0].password.clone()
+    }
+
+    fn has_field_error(result: Result<Response<String>, AppError>, field: &str) -> bool {
+        match result {
+            Err(AppError::InvalidFields(errors)) => errors.field_errors().contains_key(field),
+            _ => false,
+        }
+    }
+
+    #[rocket::async_test]
+    async fn sends_a_reset_token_to_a_known_account() {
+        let fixture = fixture();
+
+        forgot_password(&fixture, &fixture.user.email)
+            .await
+            .unwrap();
+
+        let sent = fixture.notifier.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, fixture.user.email);
+        let resets = fixture.resets.resets.lock().unwrap();
+        // Only the hash of the token is stored.
+        assert_eq!(resets[0].token_hash, tokens::hash(&sent[0].1));
+    }
+
+    #[rocket::async_test]
+    async fn answers_the_same_whatever_happens_to_the_reset() {
+        let unknown = fixture();
+        let down = fixture_with(FakeNotifier {
+            down: true,
+            ..FakeNotifier::default()
+        });
+        let disabled = fixture();
+        disabled.users.users.lock().unwrap()[0].disabled = true;
+
+        let answers = [
+            forgot_password(&unknown, "nobody@labranet.test")
+                .await
+                .unwrap(),
+            forgot_password(&down, &down.user.email).await.unwrap(),
+            forgot_password(&disabled, &disabled.user.email)
+                .await
+                .unwrap(),
+        ];
+
+        for answer in answers {
+            let ResponseBody::Data(message) = answer.body;
+            assert_eq!(message, "Password reset sent if the account exists");
+        }
+        assert!(sent_tokens(&unknown).is_empty());
+        assert!(sent_tokens(&disabled).is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn a_new_reset_voids_the_previous_token() {
+        let fixture = fixture();
+        forgot_password(&fixture, &fixture.user.email)
+            .await
+            .unwrap();
+        forgot_password(&fixture, &fixture.user.email)
+            .await
+            .unwrap();
+        let sent = sent_tokens(&fixture);
+
+        let first = reset_password(&fixture, &sent[0], "a new password").await;
+
+        assert!(has_field_error(first, "token"));
+        assert!(reset_password(&fixture, &sent[1], "a new password")
+            .await
+            .is_ok());
+    }
+
+    #[rocket::async_test]
+    async fn resetting_spends_the_token_and_ends_every_session() {
+        let fixture = fixture();
+        let session = login(&fixture).await;
+        forgot_password(&fixture, &fixture.user.email)
+            .await
+            .unwrap();
+        let token = sent_tokens(&fixture).remove(0);
+
+        reset_password(&fixture, &token, "a new password")
+            .await
+            .unwrap();
+        let again = reset_password(&fixture, &token, "another password").await;
+
+        assert!(has_field_error(again, "token"));
+        assert!(password::verify(
+            "a new password".to_string(),
+            stored_password(&fixture)
+        ));
+        assert!(stored(&fixture, &session.refresh_token).revoked);
+        assert_eq!(fixture.bus.published::<SessionsRevokedEvent>().len(), 1);
+    }
+
+    #[rocket::async_test]
+    async fn a_rejected_password_does_not_spend_the_token() {
+        let fixture = fixture();
+        forgot_password(&fixture, &fixture.user.email)
+            .await
+            .unwrap();
+        let token = sent_tokens(&fixture).remove(0);
+
+        let short = reset_password(&fixture, &token, "short").await;
+
+        assert!(has_field_error(short, "new_password"));
+        assert!(reset_password(&fixture, &token, "long enough")
+            .await
+            .is_ok());
+    }
+
+    #[rocket::async_test]
+    async fn refuses_an_expired_token() {
+        let fixture = fixture();
+        forgot_password(&fixture, &fixture.user.email)
+            .await
+            .unwrap();
+        let token = sent_tokens(&fixture).remove(0);
+        fixture.resets.resets.lock().unwrap()[0].expires_at =
+            DateTime::from_millis(DateTime::now().timestamp_millis() - 1000);
+
+        let expired = reset_password(&fixture, &token, "a new password").await;
+
+        assert!(has_field_error(expired, "token"));
+        assert!(password::verify(
+            "correct horse".to_string(),
+            stored_password(&fixture)
+        ));
     }
 }
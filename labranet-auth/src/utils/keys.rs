@@ -0,0 +1,117 @@
+//! The Ed25519 keys access tokens are signed with. Every `<kid>.pem` file in
+//! `JWT_KEYS_DIR` (default `keys`) holds one PKCS#8 private key, as made by
+//! `openssl genpkey -algorithm ed25519 -out keys/<kid>.pem`. Tokens are signed with
+//! the key `JWT_SIGNING_KEY_ID`, or with the last one in name order when it is not
+//! set, and the public halves of all keys are published as a JWKS.
+//!
+//! To rotate, add the new key and switch to it; delete the old key only once the
+//! last access token it signed has expired. Without any key a throwaway one is
+//! generated at startup, which is enough on one machine but voids every token on
+//! restart.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use dotenvy::dotenv;
+use jsonwebtoken::errors::Error;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use labranet_common::jwt::Claims;
+use mongodb::bson::oid::ObjectId;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+
+pub struct SigningKeys {
+    kid: String,
+    encoding_key: EncodingKey,
+    jwks: JwkSet,
+}
+impl SigningKeys {
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        let dir = PathBuf::from(env::var("JWT_KEYS_DIR").unwrap_or_else(|_| "keys".to_string()));
+        let mut keys = read_keys(&dir);
+        if keys.is_empty() {
+            println!("No signing keys in {}, using a throwaway key", dir.display());
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .expect("Cannot generate a signing key.");
+            keys.push((ObjectId::new().to_hex(), pkcs8.as_ref().to_vec()));
+        }
+        let kid = env::var("JWT_SIGNING_KEY_ID").unwrap_or_else(|_| keys.last().unwrap().0.clone());
+        let (_, pkcs8) = keys
+            .iter()
+            .find(|(key_id, _)| *key_id == kid)
+            .expect("JWT_SIGNING_KEY_ID must name a key in JWT_KEYS_DIR.");
+        SigningKeys {
+            encoding_key: EncodingKey::from_ed_der(pkcs8),
+            jwks: JwkSet {
+                keys: keys.iter().map(|(kid, pkcs8)| public_jwk(kid, pkcs8)).collect(),
+            },
+            kid,
+        }
+    }
+
+    /// The public keys of every configured key, current or not.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+
+    pub fn create_jwt(&self, claims: &Claims) -> Result<String, Error> {
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(self.kid.clone());
+        encode(&header, claims, &self.encoding_key)
+    }
+}
+
+/// The PKCS#8 keys in `dir` by kid, sorted by kid; a missing directory has none.
+fn read_keys(dir: &Path) -> Vec<(String, Vec<u8>)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut keys = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "pem"))
+        .map(|path| {
+            let kid = path.file_stem().unwrap().to_string_lossy().to_string();
+            let pem = fs::read_to_string(&path).expect("Cannot read signing key.");
+            let pkcs8 = pem_to_der(&pem)
+                .filter(|der| Ed25519KeyPair::from_pkcs8_maybe_unchecked(der).is_ok())
+                .unwrap_or_else(|| panic!("{} is not an Ed25519 PKCS#8 key", path.display()));
+            (kid, pkcs8)
+        })
+        .collect::<Vec<(String, Vec<u8>)>>();
+    keys.sort_by(|a, b| a.0.cmp(&b.0));
+    keys
+}
+
+fn pem_to_der(pem: &str) -> Option<Vec<u8>> {
+    let body = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect::<String>();
+    STANDARD.decode(body.trim()).ok()
+}
+
+fn public_jwk(kid: &str, pkcs8: &[u8]) -> Jwk {
+    let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(pkcs8).unwrap();
+    Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(KeyAlgorithm::EdDSA),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(pair.public_key().as_ref()),
+        }),
+    }
+}
@@ -1 +1,3 @@
-pub mod password;
\ No newline at end of file
+pub mod keys;
+pub mod password;
+pub mod tokens;
\ No newline at end of file
@@ -0,0 +1,49 @@
+use std::env;
+
+use dotenvy::dotenv;
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+const TOKEN_LENGTH: usize = 64;
+
+/// How long the tokens handed out at login, refresh and password reset stay valid.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenConfig {
+    pub access_ttl: chrono::Duration,
+    pub refresh_ttl: chrono::Duration,
+    pub reset_ttl: chrono::Duration,
+}
+impl TokenConfig {
+    /// Reads `ACCESS_TOKEN_TTL_SECONDS`, `REFRESH_TOKEN_TTL_SECONDS` and
+    /// `PASSWORD_RESET_TTL_SECONDS`, defaulting to 15 minutes, 30 days and 1 hour.
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        TokenConfig {
+            access_ttl: seconds("ACCESS_TOKEN_TTL_SECONDS", 15 * 60),
+            refresh_ttl: seconds("REFRESH_TOKEN_TTL_SECONDS", 30 * 24 * 60 * 60),
+            reset_ttl: seconds("PASSWORD_RESET_TTL_SECONDS", 60 * 60),
+        }
+    }
+}
+
+fn seconds(name: &str, default: i64) -> chrono::Duration {
+    let value = env::var(name)
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(default);
+    chrono::Duration::seconds(value)
+}
+
+/// A fresh opaque refresh or password reset token; only its [`hash`] is stored.
+pub fn generate() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+pub fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+use crate::entities::users::User;
+
+/// A user as the API shows it; the password hash never leaves the service.
+#[derive(Debug, Serialize, Clone)]
+pub struct UserView {
+    pub id: String,
+    pub email: String,
+    pub mobile: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub role: String,
+    pub disabled: bool,
+}
+impl From<User> for UserView {
+    fn from(user: User) -> Self {
+        UserView {
+            id: user._id.to_hex(),
+            email: user.email,
+            mobile: user.mobile,
+            first_name: user.first_name,
+            last_name: user.last_name,
+            role: user.role,
+            disabled: user.disabled,
+        }
+    }
+}
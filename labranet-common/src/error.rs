@@ -0,0 +1,168 @@
+//! The error every use case returns. It renders itself as a JSON body carrying a
+//! stable `code` clients can branch on, next to a human readable `message`:
+//!
+//! `{"error":{"code":"not_found","message":"Not Found Room"}}`
+
+use std::io::Cursor;
+
+use jsonwebtoken::errors::Error as JwtError;
+use mongodb::bson::oid::Error as ObjectIdError;
+use mongodb::error::Error as MongoError;
+use rocket::http::{ContentType, Status};
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use rocket::serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+use validator::ValidationErrors;
+
+use crate::repository::RepoError;
+use crate::response::{ResponseError, ResponseErrorBody};
+use crate::validation::field_messages;
+
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("{0}")]
+    Validation(String),
+    /// A model failed its field rules; rendered as 422 with the messages of every field.
+    #[error("Validation failed")]
+    InvalidFields(ValidationErrors),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    /// `details` carries whatever the conflict was with, e.g. the overlapping bookings.
+    #[error("{message}")]
+    Conflict {
+        message: String,
+        details: Option<Value>,
+    },
+    #[error("{0}")]
+    Internal(String),
+    /// The database could not be reached even after retrying.
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+impl AppError {
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::Conflict {
+            message: message.into(),
+            details: None,
+        }
+    }
+    pub fn status(&self) -> Status {
+        match self {
+            AppError::Validation(_) => Status::BadRequest,
+            AppError::InvalidFields(_) => Status::UnprocessableEntity,
+            AppError::NotFound(_) => Status::NotFound,
+            AppError::Unauthorized(_) => Status::Unauthorized,
+            AppError::Forbidden(_) => Status::Forbidden,
+            AppError::Conflict { .. } => Status::Conflict,
+            AppError::Internal(_) => Status::InternalServerError,
+            AppError::Unavailable(_) => Status::ServiceUnavailable,
+        }
+    }
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Validation(_) => "validation",
+            AppError::InvalidFields(_) => "invalid_fields",
+            AppError::NotFound(_) => "not_found",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::Conflict { .. } => "conflict",
+            AppError::Internal(_) => "internal",
+            AppError::Unavailable(_) => "unavailable",
+        }
+    }
+    fn details(&self) -> Option<Value> {
+        match self {
+            AppError::InvalidFields(errors) => serde_json::to_value(field_messages(errors)).ok(),
+            AppError::Conflict { details, .. } => details.clone(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+struct AppErrorBody {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Value>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+struct AppErrorResponse {
+    error: AppErrorBody,
+}
+
+impl<'r> Responder<'r, 'static> for AppError {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        if let AppError::Internal(message) = &self {
+            println!("Internal error: {}", message);
+        }
+        let body = serde_json::to_string(&AppErrorResponse {
+            error: AppErrorBody {
+                code: self.code(),
+                message: self.to_string(),
+                details: self.details(),
+            },
+        })
+        .map_err(|_| Status::InternalServerError)?;
+        Response::build()
+            .status(self.status())
+            .header(ContentType::JSON)
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        AppError::InvalidFields(errors)
+    }
+}
+
+impl From<RepoError> for AppError {
+    fn from(err: RepoError) -> Self {
+        match err {
+            RepoError::Unavailable(message) => {
+                println!("database unavailable: {}", message);
+                AppError::Unavailable("Database is unavailable, try again later".to_string())
+            }
+            RepoError::Duplicate(_) => AppError::conflict("Already exists"),
+            RepoError::Database(message) => AppError::Internal(format!("database error: {}", message)),
+        }
+    }
+}
+
+impl From<MongoError> for AppError {
+    fn from(err: MongoError) -> Self {
+        AppError::Internal(format!("database error: {}", err))
+    }
+}
+
+impl From<ObjectIdError> for AppError {
+    fn from(_: ObjectIdError) -> Self {
+        AppError::Validation("Invalid id".to_string())
+    }
+}
+
+impl From<JwtError> for AppError {
+    fn from(err: JwtError) -> Self {
+        AppError::Unauthorized(format!("Error validating JWT token - {}", err))
+    }
+}
+
+/// Lets a use case turn the rejection of the `JWT` guard into an error with `?`.
+impl From<ResponseError<String>> for AppError {
+    fn from(err: ResponseError<String>) -> Self {
+        let ResponseErrorBody::Error(message) = err.error;
+        AppError::Unauthorized(message)
+    }
+}
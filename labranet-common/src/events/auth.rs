@@ -0,0 +1,52 @@
+use rocket::serde::{Deserialize, Serialize};
+
+use super::Event;
+
+/// One access token was revoked, e.g. on logout.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct TokenRevokedEvent {
+    pub jti: String,
+    /// When the token expires anyway; it need not be remembered after that.
+    pub exp: usize,
+}
+impl Event for TokenRevokedEvent {
+    const SUBJECT: &'static str = "token:revoked";
+}
+
+/// Every access token issued for the login session `sid` was revoked.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct SessionRevokedEvent {
+    pub sid: String,
+    /// When the last token of the session expires anyway.
+    pub exp: usize,
+}
+impl Event for SessionRevokedEvent {
+    const SUBJECT: &'static str = "session:revoked";
+}
+
+/// Every access token of the user issued before `revoked_before` was revoked.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct SessionsRevokedEvent {
+    pub user_id: String,
+    pub revoked_before: usize,
+}
+impl Event for SessionsRevokedEvent {
+    const SUBJECT: &'static str = "sessions:revoked";
+}
+
+/// The email, role or status of a user changed, or the user was deleted.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct UserChangedEvent {
+    pub id: String,
+    pub email: String,
+    pub role: String,
+    pub disabled: bool,
+    pub deleted: bool,
+}
+impl Event for UserChangedEvent {
+    const SUBJECT: &'static str = "user:changed";
+}
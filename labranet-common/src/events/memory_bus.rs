@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use rocket::tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use super::{Event, EventBus, EventError, EventMessage, EventSubscription};
+
+struct MemorySubscriber {
+    subject: String,
+    queue_group: String,
+    sender: UnboundedSender<EventMessage>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    subscribers: Vec<MemorySubscriber>,
+    cursors: HashMap<(String, String), usize>,
+    published: Vec<EventMessage>,
+}
+
+/// In-process stand-in for `NatsBus`. Like NATS, every queue group subscribed to a
+/// subject receives each message once, rotating between the group's members. All
+/// published messages are also recorded so tests can assert on what was emitted.
+#[derive(Clone, Default)]
+pub struct MemoryBus {
+    state: Arc<Mutex<MemoryState>>,
+}
+impl MemoryBus {
+    pub fn new() -> Self {
+        MemoryBus::default()
+    }
+
+    /// Every event of type `E` published on this bus so far, oldest first.
+    pub fn published<E: Event>(&self) -> Vec<E> {
+        let state = self.state.lock().unwrap();
+        state
+            .published
+            .iter()
+            .filter(|message| message.subject == E::SUBJECT)
+            .flat_map(|message| serde_json::from_slice::<E>(&message.data))
+            .collect::<Vec<E>>()
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().published.clear();
+    }
+}
+
+pub struct MemorySubscription {
+    receiver: UnboundedReceiver<EventMessage>,
+}
+
+#[rocket::async_trait]
+impl EventSubscription for MemorySubscription {
+    async fn next(&mut self) -> Option<EventMessage> {
+        self.receiver.recv().await
+    }
+}
+
+#[rocket::async_trait]
+impl EventBus for MemoryBus {
+    async fn publish(&self, subject: &str, data: Vec<u8>) -> Result<(), EventError> {
+        let message = EventMessage {
+            subject: subject.to_string(),
+            data,
+        };
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        state
+            .subscribers
+            .retain(|subscriber| !subscriber.sender.is_closed());
+        state.published.push(message.clone());
+
+        let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, subscriber) in state.subscribers.iter().enumerate() {
+            if subscriber.subject == subject {
+                groups
+                    .entry(subscriber.queue_group.clone())
+                    .or_default()
+                    .push(index);
+            }
+        }
+        for (queue_group, members) in groups {
+            let cursor = state
+                .cursors
+                .entry((subject.to_string(), queue_group))
+                .or_insert(0);
+            let member = members[*cursor % members.len()];
+            *cursor += 1;
+            // The receiver can only be gone if it was dropped after the retain above.
+            let _ = state.subscribers[member].sender.send(message.clone());
+        }
+        Ok(())
+    }
+    async fn queue_subscribe(
+        &self,
+        subject: &str,
+        queue_group: &str,
+    ) -> Result<Box<dyn EventSubscription>, EventError> {
+        let (sender, receiver) = unbounded_channel::<EventMessage>();
+        self.state.lock().unwrap().subscribers.push(MemorySubscriber {
+            subject: subject.to_string(),
+            queue_group: queue_group.to_string(),
+            sender,
+        });
+        Ok(Box::new(MemorySubscription { receiver }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rocket::serde::{Deserialize, Serialize};
+    use rocket::tokio::{sync::mpsc::unbounded_channel, time::timeout};
+
+    use super::*;
+    use crate::events::{Listener, Publisher};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(crate = "rocket::serde")]
+    struct Pinged {
+        id: u32,
+    }
+    impl Event for Pinged {
+        const SUBJECT: &'static str = "test:pinged";
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(crate = "rocket::serde")]
+    struct Ponged {
+        id: u32,
+    }
+    impl Event for Ponged {
+        const SUBJECT: &'static str = "test:ponged";
+    }
+
+    async fn received(subscription: &mut Box<dyn EventSubscription>) -> Option<Pinged> {
+        let message = timeout(Duration::from_millis(50), subscription.next())
+            .await
+            .ok()??;
+        serde_json::from_slice(&message.data).ok()
+    }
+
+    #[rocket::async_test]
+    async fn records_published_events_by_type() {
+        let bus = MemoryBus::new();
+        let publisher = Publisher::new(Arc::new(bus.clone()));
+        publisher.publish(&Pinged { id: 1 }).await.unwrap();
+        publisher.publish(&Ponged { id: 2 }).await.unwrap();
+        publisher.publish(&Pinged { id: 3 }).await.unwrap();
+
+        assert_eq!(bus.published::<Pinged>(), vec![Pinged { id: 1 }, Pinged { id: 3 }]);
+        assert_eq!(bus.published::<Ponged>(), vec![Ponged { id: 2 }]);
+        bus.clear();
+        assert!(bus.published::<Pinged>().is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn delivers_each_event_once_per_queue_group() {
+        let bus = MemoryBus::new();
+        let mut first = bus.queue_subscribe(Pinged::SUBJECT, "rooms").await.unwrap();
+        let mut second = bus.queue_subscribe(Pinged::SUBJECT, "rooms").await.unwrap();
+        let mut other = bus.queue_subscribe(Pinged::SUBJECT, "reservations").await.unwrap();
+        let publisher = Publisher::new(Arc::new(bus.clone()));
+        publisher.publish(&Pinged { id: 1 }).await.unwrap();
+        publisher.publish(&Pinged { id: 2 }).await.unwrap();
+
+        assert_eq!(received(&mut first).await, Some(Pinged { id: 1 }));
+        assert_eq!(received(&mut second).await, Some(Pinged { id: 2 }));
+        assert_eq!(received(&mut first).await, None);
+        assert_eq!(received(&mut second).await, None);
+        assert_eq!(received(&mut other).await, Some(Pinged { id: 1 }));
+        assert_eq!(received(&mut other).await, Some(Pinged { id: 2 }));
+    }
+
+    #[rocket::async_test]
+    async fn skips_other_subjects_and_dropped_subscribers() {
+        let bus = MemoryBus::new();
+        let dropped = bus.queue_subscribe(Pinged::SUBJECT, "rooms").await.unwrap();
+        let mut kept = bus.queue_subscribe(Pinged::SUBJECT, "rooms").await.unwrap();
+        drop(dropped);
+        let publisher = Publisher::new(Arc::new(bus.clone()));
+        publisher.publish(&Ponged { id: 1 }).await.unwrap();
+        publisher.publish(&Pinged { id: 2 }).await.unwrap();
+        publisher.publish(&Pinged { id: 3 }).await.unwrap();
+
+        assert_eq!(received(&mut kept).await, Some(Pinged { id: 2 }));
+        assert_eq!(received(&mut kept).await, Some(Pinged { id: 3 }));
+    }
+
+    #[rocket::async_test]
+    async fn listener_hands_events_to_the_handler() {
+        let bus = MemoryBus::new();
+        let (sender, mut receiver) = unbounded_channel::<Pinged>();
+        let listener = Listener::new(Arc::new(bus.clone()), "rooms".to_string());
+        listener
+            .listen(move |event: Pinged| {
+                let sender = sender.clone();
+                async move {
+                    sender.send(event).unwrap();
+                    Ok(())
+                }
+            })
+            .await
+            .unwrap();
+        Publisher::new(Arc::new(bus.clone()))
+            .publish(&Pinged { id: 7 })
+            .await
+            .unwrap();
+
+        let handled = timeout(Duration::from_secs(1), receiver.recv()).await.unwrap();
+        assert_eq!(handled, Some(Pinged { id: 7 }));
+    }
+}
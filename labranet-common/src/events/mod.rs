@@ -0,0 +1,202 @@
+pub mod auth;
+pub mod memory_bus;
+pub mod nats_bus;
+pub mod reservations;
+pub mod rooms;
+
+use std::env;
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use dotenvy::dotenv;
+use rocket::serde::{de::DeserializeOwned, Serialize};
+use rocket::tokio::{self, task::JoinHandle, time::sleep};
+use thiserror::Error;
+
+use crate::repository::RepoError;
+
+use self::memory_bus::MemoryBus;
+use self::nats_bus::NatsBus;
+
+/// A message exchanged between services. The subject names the NATS subject the
+/// event travels on and the payload is the JSON encoding of the implementing type.
+pub trait Event: Serialize + DeserializeOwned + Clone + Send + Sync + 'static {
+    const SUBJECT: &'static str;
+}
+
+#[derive(Debug, Error)]
+pub enum EventError {
+    #[error("event bus configuration error: {0}")]
+    Config(String),
+    #[error("event transport error: {0}")]
+    Transport(#[from] io::Error),
+    #[error("event payload error: {0}")]
+    Payload(#[from] serde_json::Error),
+    #[error("event handler error: {0}")]
+    Handler(String),
+    #[error("event handler error: {0}")]
+    Repository(#[from] RepoError),
+}
+
+/// Attempts [`Listener::listen`] makes at an event whose handler fails with a
+/// transient error before giving up on it.
+pub const HANDLER_ATTEMPTS: u32 = 3;
+/// Wait before handling a failed event again; doubled before every further attempt.
+pub const HANDLER_BACKOFF: Duration = Duration::from_millis(500);
+
+impl EventError {
+    /// Worth handling the event again, e.g. because the database was unreachable.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            EventError::Repository(err) => err.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventMessage {
+    pub subject: String,
+    pub data: Vec<u8>,
+}
+
+#[rocket::async_trait]
+pub trait EventSubscription: Send {
+    async fn next(&mut self) -> Option<EventMessage>;
+}
+
+/// Transport underneath `Publisher` and `Listener`. `NatsBus` talks to the
+/// nats-streaming server, `MemoryBus` keeps everything inside the process.
+#[rocket::async_trait]
+pub trait EventBus: Send + Sync {
+    async fn publish(&self, subject: &str, data: Vec<u8>) -> Result<(), EventError>;
+    async fn queue_subscribe(
+        &self,
+        subject: &str,
+        queue_group: &str,
+    ) -> Result<Box<dyn EventSubscription>, EventError>;
+}
+
+/// Connects to the bus named by `EVENT_BUS`. It defaults to `nats`, which needs the
+/// server at `NATS_URL`; `memory` keeps events inside the process so a service can
+/// run on one machine without nats-streaming, and must be asked for explicitly.
+pub async fn connect() -> Result<Arc<dyn EventBus>, EventError> {
+    dotenv().ok();
+    let kind = env::var("EVENT_BUS").unwrap_or_else(|_| "nats".to_string());
+    match kind.as_str() {
+        "nats" => {
+            let nats_url = env::var("NATS_URL").map_err(|_| {
+                EventError::Config("NATS_URL must be set when EVENT_BUS is nats".to_string())
+            })?;
+            Ok(Arc::new(NatsBus::connect(nats_url.as_str()).await?))
+        }
+        "memory" => {
+            println!("EVENT_BUS is memory, events stay inside this process");
+            Ok(Arc::new(MemoryBus::new()))
+        }
+        other => Err(EventError::Config(format!(
+            "EVENT_BUS must be nats or memory, got {}",
+            other
+        ))),
+    }
+}
+
+pub struct Listener {
+    pub client: Arc<dyn EventBus>,
+    pub queue_group_name: String,
+}
+impl Listener {
+    pub fn new(client: Arc<dyn EventBus>, queue_group_name: String) -> Self {
+        Listener {
+            client,
+            queue_group_name,
+        }
+    }
+
+    /// Subscribes to `E::SUBJECT` in the listener's queue group, so each event is
+    /// handled by only one instance of the service, and runs `handler` for every
+    /// event on a background task.
+    ///
+    /// Delivery is at most once: the bus does not redeliver, so a handler failing
+    /// with a transient error is retried here up to [`HANDLER_ATTEMPTS`] times and
+    /// the event is logged and dropped when it still fails or cannot be decoded.
+    pub async fn listen<E, F, Fut>(&self, handler: F) -> Result<JoinHandle<()>, EventError>
+    where
+        E: Event,
+        F: Fn(E) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), EventError>> + Send,
+    {
+        let mut subscription = self
+            .client
+            .queue_subscribe(E::SUBJECT, self.queue_group_name.as_str())
+            .await?;
+        Ok(tokio::spawn(async move {
+            while let Some(message) = subscription.next().await {
+                let result = match serde_json::from_slice::<E>(&message.data) {
+                    Ok(event) => Listener::handle(&handler, event).await,
+                    Err(err) => Err(EventError::from(err)),
+                };
+                if let Err(err) = result {
+                    println!("Event {} dropped: {}", message.subject, err);
+                }
+            }
+        }))
+    }
+
+    async fn handle<E, F, Fut>(handler: &F, event: E) -> Result<(), EventError>
+    where
+        E: Event,
+        F: Fn(E) -> Fut,
+        Fut: Future<Output = Result<(), EventError>>,
+    {
+        let mut backoff = HANDLER_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            match handler(event.clone()).await {
+                Err(err) if err.is_transient() && attempt < HANDLER_ATTEMPTS => {
+                    println!("Handling {} again after {}, attempt {}", E::SUBJECT, err, attempt);
+                    sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+pub struct Publisher {
+    pub client: Arc<dyn EventBus>,
+}
+impl Publisher {
+    pub fn new(client: Arc<dyn EventBus>) -> Self {
+        Publisher { client }
+    }
+
+    pub async fn publish<E: Event>(&self, event: &E) -> Result<(), EventError> {
+        let payload = serde_json::to_vec(event)?;
+        self.client.publish(E::SUBJECT, payload).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One test so no other test sees the variables half set.
+    #[rocket::async_test]
+    async fn connect_needs_an_explicit_memory_bus_or_a_nats_url() {
+        env::remove_var("NATS_URL");
+        env::remove_var("EVENT_BUS");
+        assert!(matches!(connect().await, Err(EventError::Config(_))));
+
+        env::set_var("EVENT_BUS", "kafka");
+        assert!(matches!(connect().await, Err(EventError::Config(_))));
+
+        env::set_var("EVENT_BUS", "memory");
+        assert!(connect().await.is_ok());
+        env::remove_var("EVENT_BUS");
+    }
+}
@@ -0,0 +1,46 @@
+use nats::asynk::{Connection, Subscription};
+
+use super::{EventBus, EventError, EventMessage, EventSubscription};
+
+pub struct NatsBus {
+    pub client: Connection,
+}
+impl NatsBus {
+    pub fn new(client: Connection) -> Self {
+        NatsBus { client }
+    }
+    pub async fn connect(nats_url: &str) -> Result<Self, EventError> {
+        let client = nats::asynk::connect(nats_url).await?;
+        Ok(NatsBus::new(client))
+    }
+}
+
+pub struct NatsSubscription {
+    subscription: Subscription,
+}
+
+#[rocket::async_trait]
+impl EventSubscription for NatsSubscription {
+    async fn next(&mut self) -> Option<EventMessage> {
+        self.subscription.next().await.map(|message| EventMessage {
+            subject: message.subject,
+            data: message.data,
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl EventBus for NatsBus {
+    async fn publish(&self, subject: &str, data: Vec<u8>) -> Result<(), EventError> {
+        self.client.publish(subject, data).await?;
+        Ok(())
+    }
+    async fn queue_subscribe(
+        &self,
+        subject: &str,
+        queue_group: &str,
+    ) -> Result<Box<dyn EventSubscription>, EventError> {
+        let subscription = self.client.queue_subscribe(subject, queue_group).await?;
+        Ok(Box::new(NatsSubscription { subscription }))
+    }
+}
@@ -0,0 +1,72 @@
+//! Reservation events. Every subject is consumed on its own task, so they can
+//! arrive out of order; `version` is the time of the change in milliseconds since
+//! the epoch and followers apply an event only when it is newer than what they hold.
+
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::reservation_status::ReservationStatus;
+
+use super::Event;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct ReservationCreatedEvent {
+    pub id: String,
+    pub rooms: Vec<String>,
+    /// RFC 3339 timestamp.
+    pub reservation_start_date: String,
+    /// RFC 3339 timestamp.
+    pub reservation_end_date: String,
+    pub status: ReservationStatus,
+    pub version: i64,
+}
+impl Event for ReservationCreatedEvent {
+    const SUBJECT: &'static str = "reservation:created";
+}
+
+/// Sent when the rooms, the window or a non-final status of a reservation changed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct ReservationUpdatedEvent {
+    pub id: String,
+    pub rooms: Vec<String>,
+    /// RFC 3339 timestamp.
+    pub reservation_start_date: String,
+    /// RFC 3339 timestamp.
+    pub reservation_end_date: String,
+    pub status: ReservationStatus,
+    pub version: i64,
+}
+impl Event for ReservationUpdatedEvent {
+    const SUBJECT: &'static str = "reservation:updated";
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct ReservationCancelledEvent {
+    pub id: String,
+    pub version: i64,
+}
+impl Event for ReservationCancelledEvent {
+    const SUBJECT: &'static str = "reservation:cancelled";
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct ReservationCompletedEvent {
+    pub id: String,
+    pub version: i64,
+}
+impl Event for ReservationCompletedEvent {
+    const SUBJECT: &'static str = "reservation:completed";
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct ReservationDeletedEvent {
+    pub id: String,
+    pub version: i64,
+}
+impl Event for ReservationDeletedEvent {
+    const SUBJECT: &'static str = "reservation:deleted";
+}
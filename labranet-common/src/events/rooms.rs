@@ -0,0 +1,56 @@
+//! Room events. Every subject is consumed on its own task, so they can arrive out
+//! of order; replicas apply an event only when its `version` is newer than theirs.
+
+use rocket::serde::{Deserialize, Serialize};
+
+use super::Event;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RoomCreatedEvent {
+    pub id: String,
+    pub floor_id: String,
+    pub room_number: String,
+    pub name: String,
+    pub price: f64,
+    /// Milliseconds since the epoch when the room was written.
+    pub version: i64,
+}
+impl Event for RoomCreatedEvent {
+    const SUBJECT: &'static str = "room:created";
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RoomUpdatedEvent {
+    pub id: String,
+    pub floor_id: String,
+    pub room_number: String,
+    pub name: String,
+    pub price: f64,
+    /// Milliseconds since the epoch when the room was written.
+    pub version: i64,
+}
+impl Event for RoomUpdatedEvent {
+    const SUBJECT: &'static str = "room:updated";
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RoomDeletedEvent {
+    pub id: String,
+    /// Milliseconds since the epoch when the room was deleted.
+    pub version: i64,
+}
+impl Event for RoomDeletedEvent {
+    const SUBJECT: &'static str = "room:deleted";
+}
+
+/// Asks labranet-rooms to publish a `RoomUpdatedEvent` for every room, so a new
+/// replica is filled with the rooms that existed before it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RoomsSyncRequestedEvent {}
+impl Event for RoomsSyncRequestedEvent {
+    const SUBJECT: &'static str = "rooms:sync-requested";
+}
@@ -0,0 +1,165 @@
+//! Public keys the `JWT` guard verifies access tokens with. Only labranet-auth holds
+//! the private keys; the other services fetch the public ones from `JWKS_URL` (the
+//! auth service's `/.well-known/jwks.json`) or read them from `JWKS_FILE`, and keep
+//! them for `JWKS_CACHE_SECONDS`. A token signed with a key that is not cached yet
+//! triggers an early reload, so a newly rotated key is picked up right away.
+
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use dotenvy::dotenv;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::DecodingKey;
+use rocket::tokio::{fs, sync::RwLock};
+use thiserror::Error;
+
+const DEFAULT_CACHE_SECONDS: u64 = 300;
+/// Shortest wait between two reloads caused by tokens with an unknown `kid`, so
+/// forged tokens cannot make every request hit the key source.
+const MIN_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum KeyError {
+    #[error("public keys unavailable: {0}")]
+    Unavailable(String),
+    #[error("unknown signing key {0}")]
+    UnknownKey(String),
+    #[error(transparent)]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+}
+
+enum KeySource {
+    Url(String),
+    File(PathBuf),
+    /// Keys handed over at startup, as labranet-auth does with its own.
+    Fixed,
+}
+
+#[derive(Default)]
+struct CachedKeys {
+    keys: HashMap<String, DecodingKey>,
+    loaded_at: Option<Instant>,
+}
+
+pub struct PublicKeys {
+    source: KeySource,
+    ttl: Duration,
+    cache: RwLock<CachedKeys>,
+}
+impl PublicKeys {
+    /// Reads `JWKS_URL`, or `JWKS_FILE` when no URL is set, and `JWKS_CACHE_SECONDS`
+    /// (default 5 minutes).
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        let source = match env::var("JWKS_URL") {
+            Ok(url) => KeySource::Url(url),
+            Err(_) => KeySource::File(PathBuf::from(
+                env::var("JWKS_FILE").expect("JWKS_URL or JWKS_FILE must be set."),
+            )),
+        };
+        let ttl = env::var("JWKS_CACHE_SECONDS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_CACHE_SECONDS);
+        PublicKeys {
+            source,
+            ttl: Duration::from_secs(ttl),
+            cache: RwLock::new(CachedKeys::default()),
+        }
+    }
+
+    pub fn from_set(set: &JwkSet) -> Self {
+        PublicKeys {
+            source: KeySource::Fixed,
+            ttl: Duration::MAX,
+            cache: RwLock::new(CachedKeys {
+                keys: decoding_keys(set),
+                loaded_at: Some(Instant::now()),
+            }),
+        }
+    }
+
+    /// The key with id `kid`, reloading the cache first when it is stale or does
+    /// not know the key.
+    pub async fn key(&self, kid: &str) -> Result<DecodingKey, KeyError> {
+        {
+            let cache = self.cache.read().await;
+            if !self.needs_reload(&cache, kid) {
+                return cache
+                    .keys
+                    .get(kid)
+                    .cloned()
+                    .ok_or_else(|| KeyError::UnknownKey(kid.to_string()));
+            }
+        }
+        let mut cache = self.cache.write().await;
+        // Another request may have reloaded while this one waited for the lock.
+        if self.needs_reload(&cache, kid) {
+            match self.load().await {
+                Ok(set) => {
+                    cache.keys = decoding_keys(&set);
+                    cache.loaded_at = Some(Instant::now());
+                }
+                // Stale keys are better than none while the source is down.
+                Err(err) if !cache.keys.is_empty() => {
+                    println!("Cannot reload public keys, keeping the cached ones: {}", err);
+                    cache.loaded_at = Some(Instant::now());
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        cache
+            .keys
+            .get(kid)
+            .cloned()
+            .ok_or_else(|| KeyError::UnknownKey(kid.to_string()))
+    }
+
+    fn needs_reload(&self, cache: &CachedKeys, kid: &str) -> bool {
+        match (&self.source, cache.loaded_at) {
+            (KeySource::Fixed, _) => false,
+            (_, None) => true,
+            (_, Some(loaded_at)) => {
+                loaded_at.elapsed() >= self.ttl
+                    || (!cache.keys.contains_key(kid) && loaded_at.elapsed() >= MIN_RELOAD_INTERVAL)
+            }
+        }
+    }
+
+    async fn load(&self) -> Result<JwkSet, KeyError> {
+        let unavailable = |err: &dyn std::fmt::Display| KeyError::Unavailable(err.to_string());
+        match &self.source {
+            KeySource::Url(url) => reqwest::get(url)
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|err| unavailable(&err))?
+                .json::<JwkSet>()
+                .await
+                .map_err(|err| unavailable(&err)),
+            KeySource::File(path) => {
+                let content = fs::read(path).await.map_err(|err| unavailable(&err))?;
+                serde_json::from_slice::<JwkSet>(&content).map_err(|err| unavailable(&err))
+            }
+            KeySource::Fixed => Err(KeyError::Unavailable("keys are fixed".to_string())),
+        }
+    }
+}
+
+/// The keys of `set` by `kid`; keys without an id or that cannot verify are skipped.
+fn decoding_keys(set: &JwkSet) -> HashMap<String, DecodingKey> {
+    set.keys
+        .iter()
+        .filter_map(|jwk| {
+            let kid = jwk.common.key_id.clone()?;
+            match DecodingKey::from_jwk(jwk) {
+                Ok(key) => Some((kid, key)),
+                Err(err) => {
+                    println!("Skipping public key {}: {}", kid, err);
+                    None
+                }
+            }
+        })
+        .collect()
+}
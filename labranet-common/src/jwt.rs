@@ -1,20 +1,33 @@
 
 use rocket::serde::{Deserialize, Serialize};
-use jsonwebtoken::{encode, decode, EncodingKey, Algorithm, Header, Validation,DecodingKey}; // 👈 New!
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation}; // 👈 New!
 use jsonwebtoken::errors::{Error, ErrorKind};
 use chrono::Utc;
 use rocket::request::{Outcome, Request, FromRequest}; // 👈 New!
 use rocket::http::Status;
-use dotenvy::dotenv;
-use std::env;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::Arc;
 
+use mongodb::bson::oid::ObjectId;
+
+use crate::error::AppError;
+use crate::jwks::{KeyError, PublicKeys};
 use crate::response::{ResponseError, ResponseErrorBody};
+use crate::permissions::Permission;
+use crate::revocations::Denylist;
+use crate::roles::Role;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Claims {
     pub subject_id: String,
     pub subject_email:String,
     pub subject_role:String,
+    /// Id of this token, the key it is revoked by.
+    pub jti: String,
+    /// The login session (refresh token family) the token was issued for.
+    pub sid: String,
+    pub iat: usize,
     pub exp: usize
 }
 
@@ -25,38 +38,14 @@ pub struct JWT {
 
 
 
-pub fn create_jwt(id: String,email:String,role:String) -> Result<String, Error> {
-    dotenv().ok();
-    let secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set."); // 👈 New!
-
-    let expiration = Utc::now().checked_add_signed(chrono::Duration::seconds(60)).expect("Invalid timestamp").timestamp();
-    
-    let claims = Claims {
-        subject_id: id,
-        subject_email:email,
-        subject_role:role,
-        exp: expiration as usize
-    }; 
-
-    let header = Header::new(Algorithm::HS512);
-
-    encode(&header, &claims, &EncodingKey::from_secret(secret.as_bytes()))
-}
-
-pub fn decode_jwt(token: String) -> Result<Claims, ErrorKind> {
-    dotenv().ok();
-    let secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set.");
+/// Verifies a bearer token with the public key named by its `kid`.
+pub async fn decode_jwt(token: &str, keys: &PublicKeys) -> Result<Claims, KeyError> {
     let token = token.trim_start_matches("Bearer").trim();
-
-    // 👇 New!
-    match decode::<Claims>(
-        &token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::new(Algorithm::HS512),
-    ) {
-        Ok(token) => Ok(token.claims),
-        Err(err) => Err(err.kind().to_owned())
-    }
+    let kid = decode_header(token)?
+        .kid
+        .ok_or_else(|| Error::from(ErrorKind::InvalidToken))?;
+    let key = keys.key(&kid).await?;
+    Ok(decode::<Claims>(token, &key, &Validation::new(Algorithm::EdDSA))?.claims)
 }
 
 
@@ -68,18 +57,35 @@ pub fn decode_jwt(token: String) -> Result<Claims, ErrorKind> {
 impl <'r> FromRequest<'r> for JWT{
     type Error = ResponseError<String>;
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ResponseError<String>>{
-        fn is_valid(key: &str) -> Result<Claims,Error> {
-            Ok(decode_jwt(String::from(key))?)
-        }
+        let keys = match req.rocket().state::<PublicKeys>() {
+            Some(keys) => keys,
+            None => {
+                let response = ResponseError { error: ResponseErrorBody::<String>::Error(String::from("Error validating JWT token - No public keys configured"))};
+                return Outcome::Error((Status::InternalServerError, response))
+            }
+        };
         match req.headers().get_one("authorization") {
             None => {
                 let response = ResponseError { error: ResponseErrorBody::<String>::Error(String::from("Error validating JWT token - No token provided"))};
                 
                 Outcome::Error((Status::Unauthorized, response)) 
             },
-            Some(key) => match is_valid(key) {
+            Some(key) => match decode_jwt(key, keys).await {
+                Ok(claims) if is_revoked(req, &claims) => {
+                    let response = ResponseError { error: ResponseErrorBody::<String>::Error(String::from("Error validating JWT token - Revoked Token"))};
+                    Outcome::Error((Status::Unauthorized, response))
+                },
                 Ok(claims) => Outcome::Success(JWT {claims}),
-                Err(err) => match &err.kind() {
+                Err(KeyError::Unavailable(err)) => {
+                    println!("Cannot verify JWT token: {}", err);
+                    let response = ResponseError { error: ResponseErrorBody::<String>::Error(String::from("Error validating JWT token - Public keys unavailable"))};
+                    Outcome::Error((Status::ServiceUnavailable, response))
+                },
+                Err(KeyError::UnknownKey(_)) => {
+                    let response = ResponseError { error: ResponseErrorBody::<String>::Error(String::from("Error validating JWT token - Invalid Token"))};
+                    Outcome::Error((Status::Unauthorized, response))
+                },
+                Err(KeyError::Jwt(err)) => match &err.kind() {
                     jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
                         let response = ResponseError { error: ResponseErrorBody::<String>::Error(format!("Error validating JWT token - Expired Token"))};
                         Outcome::Error((Status::Unauthorized, response))
@@ -96,4 +102,71 @@ impl <'r> FromRequest<'r> for JWT{
             },
         }
     }
-}
\ No newline at end of file
+}
+
+
+/// Whether the service's `Denylist`, if it manages one, holds the token.
+fn is_revoked(req: &Request<'_>, claims: &Claims) -> bool {
+    req.rocket()
+        .state::<Arc<Denylist>>()
+        .is_some_and(|denylist| denylist.is_revoked(claims))
+}
+
+impl Claims {
+    /// Claims of a new access token for the user's session `session_id` that expires `expires_in` from now.
+    pub fn new(id: String,email:String,role:String,session_id:String,expires_in:chrono::Duration) -> Self {
+        let now = Utc::now();
+        let expiration = now.checked_add_signed(expires_in).expect("Invalid timestamp").timestamp();
+        Claims {
+            subject_id: id,
+            subject_email:email,
+            subject_role:role,
+            jti: ObjectId::new().to_hex(),
+            sid: session_id,
+            iat: now.timestamp() as usize,
+            exp: expiration as usize
+        }
+    }
+    pub fn role(&self) -> Option<Role> {
+        Role::from_str(self.subject_role.as_str()).ok()
+    }
+    /// Whether the caller's role grants `permission`; an unknown role grants nothing.
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.role().is_some_and(|role| role.has_permission(permission))
+    }
+}
+
+/// Rejection of an authorization guard, as the `AppError` use cases hand back.
+fn reject<T>(error: AppError) -> Outcome<T, AppError> {
+    Outcome::Error((error.status(), error))
+}
+
+/// Runs the `JWT` guard and checks the caller with `allowed`. A missing or invalid
+/// token is rejected with 401 and a caller `allowed` refuses with 403.
+async fn authorize(req: &Request<'_>, allowed: impl Fn(&Claims) -> bool) -> Outcome<JWT, AppError> {
+    match req.guard::<JWT>().await {
+        Outcome::Success(jwt) if allowed(&jwt.claims) => Outcome::Success(jwt),
+        Outcome::Success(_) => reject(AppError::Forbidden(String::from("Forbidden"))),
+        Outcome::Error((status, _)) if status == Status::ServiceUnavailable => reject(AppError::Unavailable(String::from("Public keys unavailable, try again later"))),
+        Outcome::Error((_, response)) => reject(AppError::from(response)),
+        Outcome::Forward(status) => Outcome::Forward(status),
+    }
+}
+
+/// Request guard for routes that need the permission `P`, e.g.
+/// `key: Result<Authorized<RoomsWrite>, AppError>`.
+#[derive(Debug)]
+pub struct Authorized<P: Permission> {
+    pub jwt: JWT,
+    permission: PhantomData<P>,
+}
+
+#[rocket::async_trait]
+impl <'r, P: Permission> FromRequest<'r> for Authorized<P>{
+    type Error = AppError;
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, AppError>{
+        authorize(req, |claims| claims.has_permission(P::NAME))
+            .await
+            .map(|jwt| Authorized { jwt, permission: PhantomData })
+    }
+}
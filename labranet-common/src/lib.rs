@@ -1,4 +1,12 @@
+pub mod error;
+pub mod jwks;
 pub mod jwt;
+pub mod pagination;
+pub mod permissions;
+pub mod repository;
+pub mod reservation_status;
 pub mod response;
+pub mod revocations;
 pub mod roles;
+pub mod validation;
 pub mod events;
@@ -0,0 +1,224 @@
+//! Paging and sorting shared by the list endpoints of every service. Handlers
+//! take a `PageQuery` from the query string, use cases check it with `validate`
+//! and repositories push the resulting `Pagination` down to their queries.
+
+use rocket::FromForm;
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::validation::{field_error, ValidationErrors};
+
+pub const DEFAULT_PAGE_LIMIT: u64 = 20;
+pub const MAX_PAGE_LIMIT: u64 = 100;
+/// Keeps the number of skipped documents, and with it the query cost, bounded.
+pub const MAX_PAGE: u64 = 10_000;
+
+/// `?page=2&limit=50&sort=-price`. Pages start at 1; a leading `-` sorts descending.
+#[derive(Debug, Clone, Default, FromForm)]
+pub struct PageQuery {
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+    pub sort: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pagination {
+    pub page: u64,
+    pub limit: u64,
+    pub sort_field: String,
+    /// 1 for ascending, -1 for descending, as Mongo expects.
+    pub sort_order: i32,
+}
+impl Pagination {
+    pub fn skip(&self) -> u64 {
+        self.page.saturating_sub(1).saturating_mul(self.limit)
+    }
+}
+
+impl PageQuery {
+    /// Checks the query against the fields a list can be sorted by. Without a
+    /// `sort` the list is ordered by `_id`, i.e. by creation time. Errors are keyed
+    /// by query parameter, like those of the models.
+    pub fn validate(&self, sortable: &[&str]) -> Result<Pagination, AppError> {
+        let mut errors = ValidationErrors::new();
+        match self.page {
+            Some(page) if page == 0 || page > MAX_PAGE => errors.add(
+                "page",
+                field_error("range", format!("page must be between 1 and {}", MAX_PAGE)),
+            ),
+            _ => {}
+        }
+        match self.limit {
+            Some(limit) if limit == 0 || limit > MAX_PAGE_LIMIT => errors.add(
+                "limit",
+                field_error("range", format!("limit must be between 1 and {}", MAX_PAGE_LIMIT)),
+            ),
+            _ => {}
+        }
+        let (sort_field, sort_order) = match &self.sort {
+            Some(sort) => match sort.strip_prefix('-') {
+                Some(field) => (field.to_string(), -1),
+                None => (sort.to_string(), 1),
+            },
+            None => ("_id".to_string(), 1),
+        };
+        if sort_field != "_id" && !sortable.contains(&sort_field.as_str()) {
+            errors.add(
+                "sort",
+                field_error("sort", format!("sort must be one of {}", sortable.join(", "))),
+            );
+        }
+        match errors.is_empty() {
+            true => Ok(Pagination {
+                page: self.page.unwrap_or(1),
+                limit: self.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+                sort_field,
+                sort_order,
+            }),
+            false => Err(AppError::InvalidFields(errors)),
+        }
+    }
+}
+
+/// One page of a list together with what a client needs to fetch the rest.
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub page: u64,
+    pub limit: u64,
+    pub next_page: Option<u64>,
+}
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total: u64, pagination: &Pagination) -> Self {
+        let next_page = match pagination.skip() + (items.len() as u64) < total {
+            true => Some(pagination.page + 1),
+            false => None,
+        };
+        Page {
+            items,
+            total,
+            page: pagination.page,
+            limit: pagination.limit,
+            next_page,
+        }
+    }
+
+    /// The same page with every item converted, e.g. into its response view.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        let items = self.items.into_iter().map(f).collect();
+        Page {
+            items,
+            total: self.total,
+            page: self.page,
+            limit: self.limit,
+            next_page: self.next_page,
+        }
+    }
+
+    /// The same page with its items replaced, e.g. by a richer view of them.
+    pub fn with_items<U>(self, items: Vec<U>) -> Page<U> {
+        Page {
+            items,
+            total: self.total,
+            page: self.page,
+            limit: self.limit,
+            next_page: self.next_page,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SORTABLE: [&str; 2] = ["name", "price"];
+
+    fn query(page: Option<u64>, limit: Option<u64>, sort: Option<&str>) -> PageQuery {
+        PageQuery {
+            page,
+            limit,
+            sort: sort.map(str::to_string),
+        }
+    }
+
+    fn invalid_fields(query: PageQuery) -> Vec<String> {
+        match query.validate(&SORTABLE) {
+            Err(AppError::InvalidFields(errors)) => {
+                let mut fields: Vec<String> =
+                    errors.field_errors().keys().map(|field| field.to_string()).collect();
+                fields.sort();
+                fields
+            }
+            other => panic!("expected field errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults_to_the_first_page_by_creation_time() {
+        let pagination = query(None, None, None).validate(&SORTABLE).unwrap();
+
+        assert_eq!(
+            pagination,
+            Pagination {
+                page: 1,
+                limit: DEFAULT_PAGE_LIMIT,
+                sort_field: "_id".to_string(),
+                sort_order: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn sorts_descending_on_a_leading_dash() {
+        let pagination = query(Some(3), Some(50), Some("-price")).validate(&SORTABLE).unwrap();
+
+        assert_eq!((pagination.sort_field.as_str(), pagination.sort_order), ("price", -1));
+        assert_eq!(pagination.skip(), 100);
+    }
+
+    #[test]
+    fn reports_each_invalid_parameter_on_its_own_field() {
+        assert_eq!(
+            invalid_fields(query(Some(0), Some(0), Some("password"))),
+            vec!["limit", "page", "sort"]
+        );
+        assert_eq!(invalid_fields(query(Some(MAX_PAGE + 1), None, None)), vec!["page"]);
+        assert_eq!(invalid_fields(query(None, Some(MAX_PAGE_LIMIT + 1), None)), vec!["limit"]);
+        assert_eq!(invalid_fields(query(None, None, Some("-"))), vec!["sort"]);
+    }
+
+    #[test]
+    fn accepts_the_largest_page_and_limit() {
+        let pagination = query(Some(MAX_PAGE), Some(MAX_PAGE_LIMIT), Some("name"))
+            .validate(&SORTABLE)
+            .unwrap();
+
+        assert_eq!(pagination.skip(), (MAX_PAGE - 1) * MAX_PAGE_LIMIT);
+    }
+
+    #[test]
+    fn saturates_instead_of_overflowing() {
+        let pagination = Pagination {
+            page: u64::MAX,
+            limit: u64::MAX,
+            sort_field: "_id".to_string(),
+            sort_order: 1,
+        };
+
+        assert_eq!(pagination.skip(), u64::MAX);
+    }
+
+    #[test]
+    fn points_to_the_next_page_while_items_remain() {
+        let pagination = query(Some(2), Some(10), None).validate(&SORTABLE).unwrap();
+
+        let middle = Page::new(vec![0; 10], 25, &pagination);
+        let last = Page::new(vec![0; 10], 20, &pagination);
+
+        assert_eq!(middle.next_page, Some(3));
+        assert_eq!(last.next_page, None);
+        assert_eq!(middle.map(|item| item + 1).items, vec![1; 10]);
+    }
+}
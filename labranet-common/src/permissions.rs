@@ -0,0 +1,83 @@
+//! Named permissions granted to each `Role`. Services check these instead of
+//! comparing role names, and the marker types below let a handler require one
+//! through the `Authorized` request guard.
+
+pub const ROOMS_READ: &str = "rooms:read";
+pub const ROOMS_WRITE: &str = "rooms:write";
+/// Read reservations made by the caller.
+pub const RESERVATIONS_READ: &str = "reservations:read";
+/// Read reservations made by anyone.
+pub const RESERVATIONS_READ_ANY: &str = "reservations:read:any";
+/// Create, change and cancel reservations made by the caller.
+pub const RESERVATIONS_WRITE: &str = "reservations:write";
+/// Change and cancel reservations made by anyone.
+pub const RESERVATIONS_WRITE_ANY: &str = "reservations:write:any";
+/// Confirm, complete or mark a reservation as a no-show.
+pub const RESERVATIONS_STATUS: &str = "reservations:status";
+/// Create, change, disable and delete user accounts.
+pub const USERS_ADMIN: &str = "users:admin";
+
+pub const ADMIN_PERMISSIONS: [&str; 8] = [
+    ROOMS_READ,
+    ROOMS_WRITE,
+    RESERVATIONS_READ,
+    RESERVATIONS_READ_ANY,
+    RESERVATIONS_WRITE,
+    RESERVATIONS_WRITE_ANY,
+    RESERVATIONS_STATUS,
+    USERS_ADMIN,
+];
+pub const WORKER_PERMISSIONS: [&str; 3] = [ROOMS_READ, RESERVATIONS_READ, RESERVATIONS_WRITE];
+
+/// A permission a route can require through `Authorized<P>`.
+pub trait Permission: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+#[derive(Debug)]
+pub struct RoomsRead;
+impl Permission for RoomsRead {
+    const NAME: &'static str = ROOMS_READ;
+}
+
+#[derive(Debug)]
+pub struct RoomsWrite;
+impl Permission for RoomsWrite {
+    const NAME: &'static str = ROOMS_WRITE;
+}
+
+#[derive(Debug)]
+pub struct ReservationsRead;
+impl Permission for ReservationsRead {
+    const NAME: &'static str = RESERVATIONS_READ;
+}
+
+#[derive(Debug)]
+pub struct ReservationsReadAny;
+impl Permission for ReservationsReadAny {
+    const NAME: &'static str = RESERVATIONS_READ_ANY;
+}
+
+#[derive(Debug)]
+pub struct ReservationsWrite;
+impl Permission for ReservationsWrite {
+    const NAME: &'static str = RESERVATIONS_WRITE;
+}
+
+#[derive(Debug)]
+pub struct ReservationsWriteAny;
+impl Permission for ReservationsWriteAny {
+    const NAME: &'static str = RESERVATIONS_WRITE_ANY;
+}
+
+#[derive(Debug)]
+pub struct ReservationsStatus;
+impl Permission for ReservationsStatus {
+    const NAME: &'static str = RESERVATIONS_STATUS;
+}
+
+#[derive(Debug)]
+pub struct UsersAdmin;
+impl Permission for UsersAdmin {
+    const NAME: &'static str = USERS_ADMIN;
+}
@@ -0,0 +1,83 @@
+//! What the Mongo repositories of every service return instead of unwrapping the
+//! driver result. Idempotent operations (reads, `$set` updates and deletes by id,
+//! upserts) go through [`retry`] so a blip in the connection does not fail the request.
+
+use std::future::{Future, IntoFuture};
+use std::time::Duration;
+
+use mongodb::error::{
+    Error as MongoError, ErrorKind, WriteFailure, RETRYABLE_WRITE_ERROR,
+    TRANSIENT_TRANSACTION_ERROR,
+};
+use rocket::tokio::time::sleep;
+use thiserror::Error;
+
+/// Attempts made by [`retry`] before the error is handed back.
+pub const RETRY_ATTEMPTS: u32 = 3;
+/// Wait before the second attempt; doubled before every further one.
+pub const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+const DUPLICATE_KEY: i32 = 11000;
+
+#[derive(Debug, Error)]
+pub enum RepoError {
+    /// The database cannot be reached; worth trying again later.
+    #[error("database unavailable: {0}")]
+    Unavailable(String),
+    #[error("duplicate key: {0}")]
+    Duplicate(String),
+    #[error("database error: {0}")]
+    Database(String),
+}
+
+impl RepoError {
+    pub fn is_transient(&self) -> bool {
+        matches!(self, RepoError::Unavailable(_))
+    }
+}
+
+impl From<MongoError> for RepoError {
+    fn from(err: MongoError) -> Self {
+        let transient = err.contains_label(RETRYABLE_WRITE_ERROR)
+            || err.contains_label(TRANSIENT_TRANSACTION_ERROR);
+        match *err.kind {
+            ErrorKind::Io(_)
+            | ErrorKind::ServerSelection { .. }
+            | ErrorKind::ConnectionPoolCleared { .. }
+            | ErrorKind::DnsResolve { .. } => RepoError::Unavailable(err.to_string()),
+            ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+                if write_error.code == DUPLICATE_KEY =>
+            {
+                RepoError::Duplicate(write_error.message.clone())
+            }
+            _ if transient => RepoError::Unavailable(err.to_string()),
+            _ => RepoError::Database(err.to_string()),
+        }
+    }
+}
+
+pub type RepoResult<T> = Result<T, RepoError>;
+
+/// Runs `operation` up to [`RETRY_ATTEMPTS`] times while it fails with a transient
+/// error, backing off exponentially from [`RETRY_BACKOFF`]. Only pass operations that
+/// can safely run twice.
+pub async fn retry<T, F, O>(operation: F) -> RepoResult<T>
+where
+    F: Fn() -> O,
+    O: IntoFuture<Output = Result<T, MongoError>>,
+    O::IntoFuture: Future<Output = Result<T, MongoError>>,
+{
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match operation().await.map_err(RepoError::from) {
+            Err(err) if err.is_transient() && attempt < RETRY_ATTEMPTS => {
+                println!("Retrying after {}, attempt {}", err, attempt);
+                sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use core::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// The status of a reservation, owned by labranet-reservations and mirrored by
+/// the services that follow reservation events.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ReservationStatus {
+    Save = 1,
+    Complete = 2,
+    Cancel = 3,
+    Confirm = 4,
+    NoShow = 5,
+}
+
+/// Statuses in which a reservation still holds its rooms.
+pub const ACTIVE_RESERVATION_STATUSES: [ReservationStatus; 2] =
+    [ReservationStatus::Save, ReservationStatus::Confirm];
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for ReservationStatus {
+    type Err = String;
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status {
+            "Save" => Ok(ReservationStatus::Save),
+            "Complete" => Ok(ReservationStatus::Complete),
+            "Cancel" => Ok(ReservationStatus::Cancel),
+            "Confirm" => Ok(ReservationStatus::Confirm),
+            "NoShow" => Ok(ReservationStatus::NoShow),
+            _ => Err(format!("Unknown reservation status {}", status)),
+        }
+    }
+}
+
+impl ReservationStatus {
+    /// Allowed moves: Save -> Confirm | Cancel, Confirm -> Complete | Cancel | NoShow.
+    /// Complete, Cancel and NoShow are final.
+    pub fn can_transition_to(&self, next: &ReservationStatus) -> bool {
+        matches!(
+            (self, next),
+            (ReservationStatus::Save, ReservationStatus::Confirm)
+                | (ReservationStatus::Save, ReservationStatus::Cancel)
+                | (ReservationStatus::Confirm, ReservationStatus::Complete)
+                | (ReservationStatus::Confirm, ReservationStatus::Cancel)
+                | (ReservationStatus::Confirm, ReservationStatus::NoShow)
+        )
+    }
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            ReservationStatus::Complete | ReservationStatus::Cancel | ReservationStatus::NoShow
+        )
+    }
+    pub fn is_active(&self) -> bool {
+        ACTIVE_RESERVATION_STATUSES.contains(self)
+    }
+    /// [`ACTIVE_RESERVATION_STATUSES`] as stored, for `$in` filters.
+    pub fn active_names() -> Vec<String> {
+        ACTIVE_RESERVATION_STATUSES
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [ReservationStatus; 5] = [
+        ReservationStatus::Save,
+        ReservationStatus::Complete,
+        ReservationStatus::Cancel,
+        ReservationStatus::Confirm,
+        ReservationStatus::NoShow,
+    ];
+
+    fn reachable_from(status: &ReservationStatus) -> Vec<ReservationStatus> {
+        ALL.iter()
+            .filter(|next| status.can_transition_to(next))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn saved_reservations_can_be_confirmed_or_cancelled() {
+        assert_eq!(
+            reachable_from(&ReservationStatus::Save),
+            vec![ReservationStatus::Cancel, ReservationStatus::Confirm]
+        );
+    }
+
+    #[test]
+    fn confirmed_reservations_can_be_completed_cancelled_or_missed() {
+        assert_eq!(
+            reachable_from(&ReservationStatus::Confirm),
+            vec![
+                ReservationStatus::Complete,
+                ReservationStatus::Cancel,
+                ReservationStatus::NoShow
+            ]
+        );
+    }
+
+    #[test]
+    fn final_statuses_cannot_move() {
+        for status in ALL.iter().filter(|status| status.is_final()) {
+            assert!(reachable_from(status).is_empty(), "{} moved", status);
+            assert!(!status.is_active());
+        }
+        assert_eq!(ALL.iter().filter(|status| status.is_final()).count(), 3);
+    }
+
+    #[test]
+    fn only_open_reservations_hold_their_rooms() {
+        let active: Vec<&ReservationStatus> =
+            ALL.iter().filter(|status| status.is_active()).collect();
+
+        assert_eq!(active, vec![&ReservationStatus::Save, &ReservationStatus::Confirm]);
+        assert_eq!(ReservationStatus::active_names(), vec!["Save", "Confirm"]);
+    }
+
+    #[test]
+    fn parses_what_it_displays() {
+        for status in ALL {
+            assert_eq!(status.to_string().parse::<ReservationStatus>(), Ok(status));
+        }
+        assert!("cancel".parse::<ReservationStatus>().is_err());
+    }
+}
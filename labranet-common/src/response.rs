@@ -1,3 +1,6 @@
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
 use serde::Serialize;
 
 #[derive(Serialize,Debug)]
@@ -19,4 +22,10 @@ pub struct Response<T> {
 #[serde(crate = "rocket::serde")]
 pub struct ResponseError<T>{
     pub error:ResponseErrorBody<T>,
-}
\ No newline at end of file
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Response<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        Json(self).respond_to(req)
+    }
+}
@@ -0,0 +1,180 @@
+//! Access tokens revoked before they expire. labranet-auth publishes an event when
+//! a user logs out and when an admin ends every session of a user; every instance
+//! of every service keeps what it heard in a [`Denylist`], which the `JWT` guard
+//! consults when the service manages one as `Arc<Denylist>`.
+//!
+//! The list lives in memory, so an instance that was down when a revocation was
+//! published misses it. That only matters until the revoked token expires.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+
+use crate::events::auth::{SessionRevokedEvent, SessionsRevokedEvent, TokenRevokedEvent};
+use crate::events::{EventBus, EventError, Listener};
+use crate::jwt::Claims;
+
+#[derive(Debug, Default)]
+pub struct Denylist {
+    /// Revoked token ids and when each token expires.
+    tokens: RwLock<HashMap<String, usize>>,
+    /// Revoked login sessions and when their last token expires.
+    session_ids: RwLock<HashMap<String, usize>>,
+    /// Users whose tokens issued before the given second are revoked. `iat` only has
+    /// whole seconds, and a token issued in the second of the revocation is kept so
+    /// the one the user gets right after a password or role change works.
+    sessions: RwLock<HashMap<String, usize>>,
+}
+impl Denylist {
+    pub fn new() -> Self {
+        Denylist::default()
+    }
+    pub fn revoke_token(&self, jti: String, exp: usize) {
+        insert_until(&self.tokens, jti, exp);
+    }
+    pub fn revoke_session(&self, sid: String, exp: usize) {
+        insert_until(&self.session_ids, sid, exp);
+    }
+    pub fn revoke_sessions(&self, user_id: String, revoked_before: usize) {
+        let mut sessions = self.sessions.write().unwrap();
+        let revoked_before = sessions
+            .get(&user_id)
+            .map_or(revoked_before, |current| revoked_before.max(*current));
+        sessions.insert(user_id, revoked_before);
+    }
+    pub fn is_revoked(&self, claims: &Claims) -> bool {
+        self.tokens.read().unwrap().contains_key(&claims.jti)
+            || self.session_ids.read().unwrap().contains_key(&claims.sid)
+            || self
+                .sessions
+                .read()
+                .unwrap()
+                .get(&claims.subject_id)
+                .is_some_and(|revoked_before| claims.iat < *revoked_before)
+    }
+}
+
+/// Adds `key` to `entries` until `exp`, dropping the entries that have expired.
+fn insert_until(entries: &RwLock<HashMap<String, usize>>, key: String, exp: usize) {
+    let now = Utc::now().timestamp() as usize;
+    let mut entries = entries.write().unwrap();
+    entries.retain(|_, entry_exp| *entry_exp >= now);
+    entries.insert(key, exp);
+}
+
+/// Feeds the revocation events into `denylist`. Unlike the events a service
+/// handles once, every instance needs every revocation, so each listens in a queue
+/// group of its own.
+pub async fn listen_revocations(
+    client: Arc<dyn EventBus>,
+    service: &str,
+    denylist: Arc<Denylist>,
+) -> Result<(), EventError> {
+    let listener = Listener::new(client, format!("{}-{}", service, ObjectId::new().to_hex()));
+    let tokens = denylist.clone();
+    listener
+        .listen(move |event: TokenRevokedEvent| {
+            tokens.revoke_token(event.jti, event.exp);
+            async { Ok(()) }
+        })
+        .await?;
+    let session_ids = denylist.clone();
+    listener
+        .listen(move |event: SessionRevokedEvent| {
+            session_ids.revoke_session(event.sid, event.exp);
+            async { Ok(()) }
+        })
+        .await?;
+    listener
+        .listen(move |event: SessionsRevokedEvent| {
+            denylist.revoke_sessions(event.user_id, event.revoked_before);
+            async { Ok(()) }
+        })
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn claims(user_id: &str) -> Claims {
+        claims_in_session(user_id, &ObjectId::new().to_hex())
+    }
+
+    fn claims_in_session(user_id: &str, sid: &str) -> Claims {
+        Claims::new(
+            user_id.to_string(),
+            "someone@labranet.test".to_string(),
+            "Worker".to_string(),
+            sid.to_string(),
+            Duration::minutes(15),
+        )
+    }
+
+    #[test]
+    fn revokes_a_single_token() {
+        let denylist = Denylist::new();
+        let revoked = claims("user");
+        let other = claims_in_session("user", &revoked.sid);
+
+        denylist.revoke_token(revoked.jti.clone(), revoked.exp);
+
+        assert!(denylist.is_revoked(&revoked));
+        assert!(!denylist.is_revoked(&other));
+    }
+
+    #[test]
+    fn revokes_every_token_of_a_session() {
+        let denylist = Denylist::new();
+        let first = claims("user");
+        let refreshed = claims_in_session("user", &first.sid);
+        let other_session = claims("user");
+
+        denylist.revoke_session(first.sid.clone(), first.exp);
+
+        assert!(denylist.is_revoked(&first));
+        assert!(denylist.is_revoked(&refreshed));
+        assert!(!denylist.is_revoked(&other_session));
+    }
+
+    #[test]
+    fn keeps_tokens_issued_in_the_second_of_a_user_wide_revocation() {
+        let denylist = Denylist::new();
+        let token = claims("user");
+
+        denylist.revoke_sessions("user".to_string(), token.iat);
+        assert!(!denylist.is_revoked(&token));
+        assert!(!denylist.is_revoked(&claims("someone else")));
+
+        denylist.revoke_sessions("user".to_string(), token.iat + 1);
+        assert!(denylist.is_revoked(&token));
+    }
+
+    #[test]
+    fn never_moves_a_user_wide_revocation_back() {
+        let denylist = Denylist::new();
+        let token = claims("user");
+
+        denylist.revoke_sessions("user".to_string(), token.iat + 1);
+        denylist.revoke_sessions("user".to_string(), token.iat - 60);
+
+        assert!(denylist.is_revoked(&token));
+    }
+
+    #[test]
+    fn forgets_tokens_once_they_expire() {
+        let denylist = Denylist::new();
+        let now = Utc::now().timestamp() as usize;
+
+        denylist.revoke_token("expired".to_string(), now - 1);
+        denylist.revoke_token("live".to_string(), now + 60);
+
+        let tokens = denylist.tokens.read().unwrap();
+        assert_eq!(tokens.keys().collect::<Vec<_>>(), vec!["live"]);
+    }
+}
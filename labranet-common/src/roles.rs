@@ -1,16 +1,42 @@
 use core::fmt;
+use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
+use crate::permissions::{ADMIN_PERMISSIONS, WORKER_PERMISSIONS};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum Role {
     Admin = 1,
     Worker = 2,
 }
 
+impl Role {
+    pub fn permissions(&self) -> &'static [&'static str] {
+        match self {
+            Role::Admin => &ADMIN_PERMISSIONS,
+            Role::Worker => &WORKER_PERMISSIONS,
+        }
+    }
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
 impl fmt::Display for Role {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
 
     }
-}
\ No newline at end of file
+}
+
+impl FromStr for Role {
+    type Err = String;
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            "Admin" => Ok(Role::Admin),
+            "Worker" => Ok(Role::Worker),
+            _ => Err(format!("Unknown role {}", role)),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Field-level validation shared by the services. Models derive `validator::Validate`
+//! and use the rules below next to the built-in ones; rules that need the database
+//! or more than one field are added by the use case with [`field_error`]. The errors
+//! reach the client as a 422 whose details map every field to its messages:
+//!
+//! `{"error":{"code":"invalid_fields","message":"Validation failed","details":{"email":["Email must be valid"]}}}`
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use mongodb::bson::{oid::ObjectId, DateTime};
+pub use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::{error::AppError, reservation_status::ReservationStatus};
+
+/// An error to add to a field by hand, e.g. `errors.add("floor_id", field_error("exists", "floor does not exist"))`.
+pub fn field_error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+/// Digits with an optional leading `+`; spaces and dashes between groups are allowed.
+pub fn phone(value: &str) -> Result<(), ValidationError> {
+    let digits = value.strip_prefix('+').unwrap_or(value);
+    let valid = digits.chars().all(|c| c.is_ascii_digit() || c == ' ' || c == '-')
+        && (8..=15).contains(&digits.chars().filter(|c| c.is_ascii_digit()).count());
+    match valid {
+        true => Ok(()),
+        false => Err(ValidationError::new("phone")),
+    }
+}
+
+pub fn object_id(value: &str) -> Result<(), ValidationError> {
+    match ObjectId::parse_str(value) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("object_id")),
+    }
+}
+
+pub fn rfc3339(value: &str) -> Result<(), ValidationError> {
+    match DateTime::parse_rfc3339_str(value) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("rfc3339")),
+    }
+}
+
+pub fn reservation_status(value: &str) -> Result<(), ValidationError> {
+    match ReservationStatus::from_str(value) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("reservation_status")),
+    }
+}
+
+/// Runs the derived rules of `model`.
+pub fn validate<M: Validate>(model: &M) -> Result<(), AppError> {
+    validate_with(model, ValidationErrors::new())
+}
+
+/// Runs the derived rules of `model` together with the `extra` errors the caller found.
+pub fn validate_with<M: Validate>(model: &M, extra: ValidationErrors) -> Result<(), AppError> {
+    let mut errors = model.validate().err().unwrap_or_default();
+    for (field, kind) in extra.into_errors() {
+        if let ValidationErrorsKind::Field(field_errors) = kind {
+            for error in field_errors {
+                errors.add(field, error);
+            }
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(AppError::InvalidFields(errors)),
+    }
+}
+
+/// `{field: [messages]}`; an error without a message falls back to its code.
+pub fn field_messages(errors: &ValidationErrors) -> BTreeMap<String, Vec<String>> {
+    errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, field_errors)| {
+            let messages = field_errors
+                .iter()
+                .map(|error| match &error.message {
+                    Some(message) => message.to_string(),
+                    None => error.code.to_string(),
+                })
+                .collect::<Vec<String>>();
+            (field.to_string(), messages)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Validate)]
+    struct Contact {
+        #[validate(length(min = 1, message = "Name is required"))]
+        name: String,
+        #[validate(custom(function = "phone"))]
+        phone: String,
+    }
+
+    fn contact(name: &str, phone: &str) -> Contact {
+        Contact {
+            name: name.to_string(),
+            phone: phone.to_string(),
+        }
+    }
+
+    fn messages(result: Result<(), AppError>) -> BTreeMap<String, Vec<String>> {
+        match result {
+            Err(AppError::InvalidFields(errors)) => field_messages(&errors),
+            other => panic!("expected field errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn accepts_phone_numbers_with_separators() {
+        for value in ["+66 81-234-5678", "0812345678", "12345678", "+123456789012345"] {
+            assert!(phone(value).is_ok(), "{} was rejected", value);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_phone_numbers() {
+        for value in [
+            "",
+            "1234567",
+            "1234567890123456",
+            "081 234 567x",
+            "++6681234567",
+            "66+812345678",
+        ] {
+            assert!(phone(value).is_err(), "{} was accepted", value);
+        }
+    }
+
+    #[test]
+    fn checks_ids_dates_and_statuses() {
+        assert!(object_id("66f0c1a2b3c4d5e6f7a8b9c0").is_ok());
+        assert!(object_id("66f0c1a2b3c4d5e6f7a8b9c").is_err());
+        assert!(rfc3339("2024-10-01T10:00:00Z").is_ok());
+        assert!(rfc3339("2024-10-01 10:00").is_err());
+        assert!(reservation_status("Confirm").is_ok());
+        assert!(reservation_status("Pending").is_err());
+    }
+
+    #[test]
+    fn passes_a_valid_model() {
+        assert!(validate(&contact("Ann", "0812345678")).is_ok());
+    }
+
+    #[test]
+    fn merges_the_caller_errors_with_the_derived_ones() {
+        let mut extra = ValidationErrors::new();
+        extra.add("name", field_error("unique", "Name is already taken"));
+        extra.add("email", field_error("exists", "Email is already registered"));
+
+        let messages = messages(validate_with(&contact("", "0812345678"), extra));
+
+        assert_eq!(messages["name"], vec!["Name is required", "Name is already taken"]);
+        assert_eq!(messages["email"], vec!["Email is already registered"]);
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn falls_back_to_the_code_without_a_message() {
+        let messages = messages(validate(&contact("Ann", "call me")));
+
+        assert_eq!(messages["phone"], vec!["phone"]);
+    }
+}
@@ -4,6 +4,7 @@ use rocket::fairing::AdHoc;
 
 
 
+#[derive(Debug, Clone)]
 pub struct  MongoDB {
     pub(crate) database: Database,
 }
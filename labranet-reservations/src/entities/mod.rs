@@ -1 +1,4 @@
-pub mod reservations;
\ No newline at end of file
+pub mod reservation_histories;
+pub mod reservations;
+pub mod room_locks;
+pub mod rooms;
\ No newline at end of file
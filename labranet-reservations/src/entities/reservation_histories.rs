@@ -0,0 +1,30 @@
+use mongodb::bson::serde_helpers::bson_datetime_as_rfc3339_string;
+use mongodb::bson::{oid::ObjectId, Bson, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ReservationAction {
+    Create,
+    Update,
+    StatusChange,
+    Delete,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReservationFieldChange {
+    pub field: String,
+    pub before: Option<Bson>,
+    pub after: Option<Bson>,
+}
+
+/// One append-only entry of a reservation's audit trail.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReservationHistory {
+    pub _id: ObjectId,
+    pub reservation_id: ObjectId,
+    pub actor_id: ObjectId,
+    pub action: ReservationAction,
+    #[serde(with = "bson_datetime_as_rfc3339_string")]
+    pub created_at: DateTime,
+    pub changes: Vec<ReservationFieldChange>,
+}
@@ -1,10 +1,34 @@
+use core::fmt;
 use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
 use mongodb::bson::serde_helpers::bson_datetime_as_rfc3339_string;
+
+use labranet_common::reservation_status::ReservationStatus;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum PriceUnit{
+    Night,
+    Hour
+}
+impl fmt::Display for PriceUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+
+    }
+}
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReservationItem {
     pub room:String,
-    pub price:f64
+    pub price:f64,
+    #[serde(default = "default_unit")]
+    pub unit:PriceUnit,
+    #[serde(default)]
+    pub quantity:i64,
+    #[serde(default)]
+    pub amount:f64
+}
+fn default_unit()->PriceUnit{
+    PriceUnit::Night
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Reservation {
@@ -13,12 +37,16 @@ pub struct Reservation {
     pub description:String,
     #[serde(with = "bson_datetime_as_rfc3339_string")]
     pub reservation_date:DateTime,
-    pub reservation_status:String,
+    pub reservation_status:ReservationStatus,
+    #[serde(default)]
+    pub cancel_reason:Option<String>,
     pub items:Vec<ReservationItem> ,
     #[serde(with = "bson_datetime_as_rfc3339_string")]
     pub reservation_start_date:DateTime,
     #[serde(with = "bson_datetime_as_rfc3339_string")]
     pub reservation_end_date:DateTime,
+    #[serde(default)]
+    pub total_price:f64,
     pub created_by :ObjectId
 
 }
\ No newline at end of file
@@ -0,0 +1,9 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoomLock {
+    pub _id: String,
+    pub owner: ObjectId,
+    pub locked_until: DateTime,
+}
@@ -0,0 +1,23 @@
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+/// Read-only copy of a labranet-rooms room, kept up to date from room events.
+/// A room deleted before its create event arrived is only a tombstone with
+/// `is_deleted` set, hence the defaults.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Room {
+    pub _id: ObjectId,
+    #[serde(default)]
+    pub floor_id: ObjectId,
+    #[serde(default)]
+    pub room_number: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub price: f64,
+    #[serde(default)]
+    pub is_deleted: bool,
+    /// `version` of the last room event applied to this copy.
+    #[serde(default)]
+    pub version: i64,
+}
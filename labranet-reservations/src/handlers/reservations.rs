@@ -1,32 +1,58 @@
-use labranet_common::{jwt::JWT, response::ResponseError};
-use rocket::{delete, get, post, put, response::status::{Created, Custom}, serde::json::Json, State};
+use labranet_common::{error::AppError, reservation_status::ReservationStatus, jwt::Authorized, pagination::{Page, PageQuery}, permissions::{ReservationsRead, ReservationsStatus, ReservationsWrite}, response::Response};
+use rocket::{delete, get, post, put, response::status::Created, serde::json::Json, State};
 
-use crate::{models::reservations::ReservationModel, usecases::reservations::ReservationUseCaseTrait};
+use crate::{models::reservations::{ReservationCancelModel, ReservationFilterModel, ReservationModel}, usecases::reservations::ReservationUseCaseTrait, views::{reservation_histories::ReservationHistoryView, reservations::ReservationView}};
 
 #[post("/reservations", format = "application/json", data = "<reservation>")]
-pub async fn new_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<JWT,ResponseError<String>>,reservation:Json<ReservationModel>)->Result<Created<String>,Custom<String>>{
+pub async fn new_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsWrite>,AppError>,reservation:Json<ReservationModel>)->Result<Created<Response<ReservationView>>,AppError>{
    let result =  reservation_use_case.new_reservation(key, reservation.into_inner()).await;
    result
 }
-#[get("/reservations",format="application/json")]
-pub async fn fetch_all_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<JWT,ResponseError<String>>)->Result<String,Custom<String>>{
-    let result = reservation_use_case.fetch_all_reservation(key).await;
+#[get("/reservations?<page>&<limit>&<sort>&<filter..>",format="application/json")]
+pub async fn fetch_all_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsRead>,AppError>,page:Option<u64>,limit:Option<u64>,sort:Option<String>,filter:ReservationFilterModel)->Result<Response<Page<ReservationView>>,AppError>{
+    let result = reservation_use_case.fetch_all_reservation(key,filter,PageQuery{page,limit,sort}).await;
     result
 }
 #[get("/reservations/<reservation_id>",format="application/json")]
-pub async fn fetch_one_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<JWT,ResponseError<String>>,reservation_id:&str)->Result<String,Custom<String>>{
+pub async fn fetch_one_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsRead>,AppError>,reservation_id:&str)->Result<Response<ReservationView>,AppError>{
     let result = reservation_use_case.fetch_one_reservation(key, reservation_id).await;
     result
 }
 #[put("/reservations/<reservation_id>",format="application/json",data="<reservation>")]
-pub async fn update_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<JWT,ResponseError<String>>,reservation:Json<ReservationModel>,reservation_id:&str)->Result<String,Custom<String>>{
+pub async fn update_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsWrite>,AppError>,reservation:Json<ReservationModel>,reservation_id:&str)->Result<Response<ReservationView>,AppError>{
     println!("Start Update {}",reservation_id);
     let result = reservation_use_case.update_reservation(key,reservation.into_inner(),reservation_id).await;
     result
 }
 
 #[delete("/reservations/<reservation_id>",format="application/json")]
-pub async fn delete_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<JWT,ResponseError<String>>,reservation_id:&str)->Result<String,Custom<String>>{
+pub async fn delete_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsWrite>,AppError>,reservation_id:&str)->Result<Response<String>,AppError>{
     let result = reservation_use_case.delete_reservation(key,reservation_id).await;
     result
-}
\ No newline at end of file
+}
+
+#[post("/reservations/<reservation_id>/confirm")]
+pub async fn confirm_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsStatus>,AppError>,reservation_id:&str)->Result<Response<ReservationView>,AppError>{
+    let result = reservation_use_case.transition_reservation(key.map(|key| key.jwt),reservation_id,ReservationStatus::Confirm,None).await;
+    result
+}
+#[post("/reservations/<reservation_id>/complete")]
+pub async fn complete_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsStatus>,AppError>,reservation_id:&str)->Result<Response<ReservationView>,AppError>{
+    let result = reservation_use_case.transition_reservation(key.map(|key| key.jwt),reservation_id,ReservationStatus::Complete,None).await;
+    result
+}
+#[post("/reservations/<reservation_id>/cancel",format="application/json",data="<cancel>")]
+pub async fn cancel_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsWrite>,AppError>,cancel:Json<ReservationCancelModel>,reservation_id:&str)->Result<Response<ReservationView>,AppError>{
+    let result = reservation_use_case.transition_reservation(key.map(|key| key.jwt),reservation_id,ReservationStatus::Cancel,Some(cancel.into_inner().reason)).await;
+    result
+}
+#[post("/reservations/<reservation_id>/no-show")]
+pub async fn no_show_reservation(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsStatus>,AppError>,reservation_id:&str)->Result<Response<ReservationView>,AppError>{
+    let result = reservation_use_case.transition_reservation(key.map(|key| key.jwt),reservation_id,ReservationStatus::NoShow,None).await;
+    result
+}
+#[get("/reservations/<reservation_id>/history",format="application/json")]
+pub async fn fetch_reservation_history(reservation_use_case:&State<Box<dyn ReservationUseCaseTrait>>,key:Result<Authorized<ReservationsRead>,AppError>,reservation_id:&str)->Result<Response<Vec<ReservationHistoryView>>,AppError>{
+    let result = reservation_use_case.fetch_reservation_history(key,reservation_id).await;
+    result
+}
@@ -3,4 +3,6 @@ pub mod entities;
 pub mod models;
 pub mod repositories;
 pub mod usecases;
-pub mod handlers;
\ No newline at end of file
+pub mod handlers;
+pub mod listeners;
+pub mod views;
\ No newline at end of file
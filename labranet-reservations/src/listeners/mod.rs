@@ -0,0 +1 @@
+pub mod rooms;
\ No newline at end of file
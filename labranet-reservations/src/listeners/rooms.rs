@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use labranet_common::events::{
+    rooms::{RoomCreatedEvent, RoomDeletedEvent, RoomUpdatedEvent, RoomsSyncRequestedEvent},
+    EventError, Listener, Publisher,
+};
+
+use crate::usecases::rooms::RoomUseCaseTrait;
+
+pub async fn listen_room_events(
+    listener: &Listener,
+    room_use_case: Arc<dyn RoomUseCaseTrait>,
+) -> Result<(), EventError> {
+    let use_case = room_use_case.clone();
+    listener
+        .listen(move |event: RoomCreatedEvent| {
+            let use_case = use_case.clone();
+            async move { use_case.room_created(event).await }
+        })
+        .await?;
+    let use_case = room_use_case.clone();
+    listener
+        .listen(move |event: RoomUpdatedEvent| {
+            let use_case = use_case.clone();
+            async move { use_case.room_updated(event).await }
+        })
+        .await?;
+    let use_case = room_use_case;
+    listener
+        .listen(move |event: RoomDeletedEvent| {
+            let use_case = use_case.clone();
+            async move { use_case.room_deleted(event).await }
+        })
+        .await?;
+    // Subscribed first so none of the rooms published in answer are missed.
+    Publisher::new(listener.client.clone())
+        .publish(&RoomsSyncRequestedEvent {})
+        .await?;
+    Ok(())
+}
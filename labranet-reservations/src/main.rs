@@ -2,20 +2,42 @@
 extern crate rocket;
 
 
+use labranet_common::error::AppError;
+use labranet_common::events;
+use labranet_common::events::Listener;
+use labranet_common::events::Publisher;
+use labranet_common::jwks::PublicKeys;
 use labranet_common::response::ResponseError;
 use labranet_common::response::ResponseErrorBody;
+use labranet_common::revocations::listen_revocations;
+use labranet_common::revocations::Denylist;
 use labranet_reservations::db::db::connect;
 use labranet_reservations::db::db::MongoDB;
+use labranet_reservations::handlers::reservations::cancel_reservation;
+use labranet_reservations::handlers::reservations::complete_reservation;
+use labranet_reservations::handlers::reservations::confirm_reservation;
 use labranet_reservations::handlers::reservations::delete_reservation;
 use labranet_reservations::handlers::reservations::fetch_all_reservation;
 use labranet_reservations::handlers::reservations::fetch_one_reservation;
+use labranet_reservations::handlers::reservations::fetch_reservation_history;
 use labranet_reservations::handlers::reservations::new_reservation;
+use labranet_reservations::handlers::reservations::no_show_reservation;
 use labranet_reservations::handlers::reservations::update_reservation;
+use labranet_reservations::repositories::reservation_histories::ReservationHistoryRepo;
+use labranet_reservations::repositories::reservation_histories::ReservationHistoryRepoTrait;
 use labranet_reservations::repositories::reservations::ReservationRepo;
 use labranet_reservations::repositories::reservations::ReservationRepoTrait;
+use labranet_reservations::repositories::room_locks::RoomLockRepo;
+use labranet_reservations::repositories::room_locks::RoomLockRepoTrait;
+use labranet_reservations::repositories::rooms::RoomRepo;
+use labranet_reservations::repositories::rooms::RoomRepoTrait;
 use labranet_reservations::usecases::reservations::ReservationUseCase;
 use labranet_reservations::usecases::reservations::ReservationUseCaseTrait;
+use labranet_reservations::usecases::rooms::RoomUseCase;
+use labranet_reservations::usecases::rooms::RoomUseCaseTrait;
+use labranet_reservations::listeners::rooms::listen_room_events;
 use rocket::http::Method;
+use std::sync::Arc;
 use rocket::serde::json::Json;
 use rocket::shield::Allow;
 use rocket::shield::Permission;
@@ -42,12 +64,24 @@ async fn rocket() -> _ {
         )
         .allow_credentials(true);
     let database = connect().await.unwrap();
+    let event_bus = events::connect().await.unwrap();
+    let publisher = Publisher::new(event_bus.clone());
+    let listener = Listener::new(event_bus.clone(), "labranet-reservations".to_string());
+    let denylist = Arc::new(Denylist::new());
+    listen_revocations(event_bus, "labranet-reservations", denylist.clone()).await.unwrap();
     let mongo = MongoDB::new(database);
-    let reservation_repo : Box<dyn ReservationRepoTrait>=Box::new(ReservationRepo::new(mongo));
-    let reservation_use_case: Box<dyn ReservationUseCaseTrait> = Box::new(ReservationUseCase::new(reservation_repo));
+    let reservation_repo : Box<dyn ReservationRepoTrait>=Box::new(ReservationRepo::new(mongo.clone()));
+    let room_lock_repo : Box<dyn RoomLockRepoTrait>=Box::new(RoomLockRepo::new(mongo.clone()));
+    let room_repo : Box<dyn RoomRepoTrait>=Box::new(RoomRepo::new(mongo.clone()));
+    let reservation_history_repo : Box<dyn ReservationHistoryRepoTrait>=Box::new(ReservationHistoryRepo::new(mongo.clone()));
+    let reservation_use_case: Box<dyn ReservationUseCaseTrait> = Box::new(ReservationUseCase::new(reservation_repo, room_lock_repo, room_repo, reservation_history_repo, publisher));
+    let room_use_case: Arc<dyn RoomUseCaseTrait> = Arc::new(RoomUseCase::new(Box::new(RoomRepo::new(mongo.clone()))));
+    listen_room_events(&listener, room_use_case).await.unwrap();
     rocket::build()
         .attach(Shield::default().enable(permission))
         .manage(reservation_use_case)
+        .manage(denylist)
+        .manage(PublicKeys::from_env())
         .manage(cors.to_cors())
         .mount(
             "/api/v1",
@@ -57,37 +91,38 @@ async fn rocket() -> _ {
                 fetch_one_reservation,
                 update_reservation,
                 delete_reservation,
+                confirm_reservation,
+                complete_reservation,
+                cancel_reservation,
+                no_show_reservation,
+                fetch_reservation_history,
                 
             ],
         )
         .register("/", 
-        catchers![unauthorized,not_found,internal_sever_error,bad_gateway]
+        catchers![unauthorized,forbidden,not_found,internal_sever_error,bad_gateway]
     )
        
 }
 
 #[catch(401)]
-pub fn unauthorized() -> Json<ResponseError<String>> {
-    let response = ResponseError{
-        error:ResponseErrorBody::<String>::Error("Unauthorize".to_string())
-    };
-    Json(response)
+pub fn unauthorized() -> AppError {
+    AppError::Unauthorized("Unauthorize".to_string())
+}
+
+#[catch(403)]
+pub fn forbidden() -> AppError {
+    AppError::Forbidden("Forbidden".to_string())
 }
 
 #[catch(404)]
-pub fn not_found() -> Json<ResponseError<String>> {
-    let response = ResponseError{
-        error:ResponseErrorBody::<String>::Error("Not Found".to_string())
-    };
-    Json(response)
+pub fn not_found() -> AppError {
+    AppError::NotFound("Not Found".to_string())
 }
 
 #[catch(500)]
-pub fn internal_sever_error() -> Json<ResponseError<String>> {
-    let response = ResponseError{
-        error:ResponseErrorBody::<String>::Error("UNKNOWN Json".to_string())
-    };
-    Json(response)
+pub fn internal_sever_error() -> AppError {
+    AppError::Internal("UNKNOWN Json".to_string())
 }
 
 #[catch(502)]
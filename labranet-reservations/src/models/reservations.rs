@@ -1,34 +1,57 @@
-use core::fmt;
+use labranet_common::validation::{reservation_status, rfc3339};
 use mongodb::bson::DateTime;
 use mongodb::bson::serde_helpers::bson_datetime_as_rfc3339_string;
+use rocket::FromForm;
 use serde::{Deserialize, Serialize};
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum ReservationStatus{
-    Save=1,
-    Complete=2,
-    Cancel=3
-}
-impl fmt::Display for ReservationStatus {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
-
-    }
-}
+use validator::Validate;
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReservationItemModel {
     pub room:String,
-    pub price:f64,
+    /// Optional; when sent it must match the unit price the service computes.
+    #[serde(default)]
+    pub price:Option<f64>,
 }
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// `reservation_start_date < reservation_end_date` is checked by the use case, which
+/// reports it on `reservation_end_date`.
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct ReservationModel {
+    #[validate(length(min = 1, max = 100, message = "Reservation name is required and at most 100 characters"))]
     pub reservation_name:String,
+    #[validate(length(min = 1, max = 1000, message = "Description is required and at most 1000 characters"))]
     pub description:String,
     #[serde(with = "bson_datetime_as_rfc3339_string")]
     pub reservation_date:DateTime,
+    #[validate(length(min = 1, message = "Reservation Item is required"))]
     pub items:Vec<ReservationItemModel> ,
     #[serde(with = "bson_datetime_as_rfc3339_string")]
     pub reservation_start_date:DateTime,
     #[serde(with = "bson_datetime_as_rfc3339_string")]
     pub reservation_end_date:DateTime,
 
-}
\ No newline at end of file
+}
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReservationConflictModel {
+    pub reservation_id:String,
+    pub room:String,
+    #[serde(with = "bson_datetime_as_rfc3339_string")]
+    pub reservation_start_date:DateTime,
+    #[serde(with = "bson_datetime_as_rfc3339_string")]
+    pub reservation_end_date:DateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReservationCancelModel {
+    pub reason:String,
+}
+
+/// Filters of `GET /reservations`. `from` and `to` are RFC 3339 dates and keep the
+/// reservations whose stay overlaps that window; `from < to` is checked by the use case.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, FromForm, Validate)]
+pub struct ReservationFilterModel {
+    #[validate(custom(function = "reservation_status", message = "Status is not a reservation status"))]
+    pub status:Option<String>,
+    #[validate(custom(function = "rfc3339", message = "From must be an RFC 3339 date"))]
+    pub from:Option<String>,
+    #[validate(custom(function = "rfc3339", message = "To must be an RFC 3339 date"))]
+    pub to:Option<String>,
+}
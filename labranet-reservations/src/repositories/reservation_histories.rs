@@ -0,0 +1,57 @@
+use labranet_common::repository::{retry, RepoError, RepoResult};
+use mongodb::bson::{doc, oid::ObjectId};
+use rocket::async_trait;
+use rocket::futures::TryStreamExt;
+
+use crate::{db::db::MongoDB, entities::reservation_histories::ReservationHistory};
+
+#[async_trait]
+pub trait ReservationHistoryRepoTrait: Send + Sync {
+    async fn add(&self, history: ReservationHistory) -> RepoResult<()>;
+    async fn find_all_by_reservation(
+        &self,
+        reservation_id: ObjectId,
+    ) -> RepoResult<Vec<ReservationHistory>>;
+}
+
+pub struct ReservationHistoryRepo {
+    mongo: MongoDB,
+}
+impl ReservationHistoryRepo {
+    pub fn new(mongo: MongoDB) -> Self {
+        ReservationHistoryRepo { mongo }
+    }
+}
+
+#[async_trait]
+impl ReservationHistoryRepoTrait for ReservationHistoryRepo {
+    /// Retried like an upsert: the entry carries its `_id`, so a duplicate key means
+    /// an earlier attempt got through.
+    async fn add(&self, history: ReservationHistory) -> RepoResult<()> {
+        let col = self
+            .mongo
+            .database
+            .collection::<ReservationHistory>("reservation_histories");
+        match retry(|| col.insert_one(&history)).await {
+            Ok(_) | Err(RepoError::Duplicate(_)) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+    async fn find_all_by_reservation(
+        &self,
+        reservation_id: ObjectId,
+    ) -> RepoResult<Vec<ReservationHistory>> {
+        let col = self
+            .mongo
+            .database
+            .collection::<ReservationHistory>("reservation_histories");
+        retry(|| async {
+            col.find(doc! {"reservation_id":reservation_id})
+                .sort(doc! {"_id":1})
+                .await?
+                .try_collect::<Vec<ReservationHistory>>()
+                .await
+        })
+        .await
+    }
+}
@@ -1,19 +1,29 @@
+use labranet_common::pagination::{Page, Pagination};
+use labranet_common::repository::{retry, RepoError, RepoResult};
+use labranet_common::reservation_status::ReservationStatus;
 use mongodb::bson::{Bson, Document};
-use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
 use mongodb::results::{DeleteResult, InsertOneResult, UpdateResult};
 use rocket::async_trait;
-use rocket::futures::StreamExt;
+use rocket::futures::TryStreamExt;
 
-use crate::{db::db::MongoDB, entities::reservations::Reservation};
+use crate::{db::db::MongoDB, entities::reservations::Reservation, models::reservations::ReservationFilterModel};
+
+/// Dates are stored as RFC 3339 strings, as the entity serializes them.
+fn rfc3339(date: DateTime) -> RepoResult<String> {
+    date.try_to_rfc3339_string()
+        .map_err(|err| RepoError::Database(err.to_string()))
+}
 
 #[async_trait]
 pub trait  ReservationRepoTrait : Send+Sync {
-    async fn add(&self,reservation:Reservation)->InsertOneResult ;
-    async fn find_all(&self)->Vec<Reservation>;
-    async fn find_all_by_user(&self,user_id:ObjectId)->Vec<Reservation>;
-    async fn find_one(&self,_id:ObjectId)->Option<Reservation>;
-    async fn update(&self,reservation:Reservation,_id:ObjectId)->UpdateResult;
-    async fn delete(&self,_id:ObjectId)->DeleteResult;
+    async fn add(&self,reservation:Reservation)->RepoResult<InsertOneResult> ;
+    async fn find_all(&self,owner:Option<ObjectId>,filter:ReservationFilterModel,pagination:&Pagination)->RepoResult<Page<Reservation>>;
+    async fn find_active_by_rooms(&self,rooms:Vec<String>)->RepoResult<Vec<Reservation>>;
+    async fn update_status(&self,_id:ObjectId,from:ReservationStatus,to:ReservationStatus,cancel_reason:Option<String>)->RepoResult<UpdateResult>;
+    async fn find_one(&self,_id:ObjectId)->RepoResult<Option<Reservation>>;
+    async fn update(&self,reservation:Reservation,_id:ObjectId,status:ReservationStatus)->RepoResult<UpdateResult>;
+    async fn delete(&self,_id:ObjectId)->RepoResult<DeleteResult>;
 }
 pub struct ReservationRepo  {
     mongo:MongoDB
@@ -27,7 +37,7 @@ impl ReservationRepo {
 #[async_trait]
 impl ReservationRepoTrait for ReservationRepo{
 
-    async fn add(&self,reservation:Reservation)->InsertOneResult{
+    async fn add(&self,reservation:Reservation)->RepoResult<InsertOneResult>{
         let col = self.mongo.database.collection::<Reservation>("reservations");
         let new_reservation=Reservation{
             _id:reservation._id,
@@ -37,62 +47,98 @@ impl ReservationRepoTrait for ReservationRepo{
             reservation_start_date:reservation.reservation_start_date,
             reservation_end_date:reservation.reservation_end_date,
             reservation_status:reservation.reservation_status,
+            cancel_reason:reservation.cancel_reason,
             items:reservation.items,
+            total_price:reservation.total_price,
             created_by:reservation.created_by
         };
         
-        col.insert_one(&new_reservation).await.unwrap()
+        Ok(col.insert_one(&new_reservation).await?)
     }
-    async fn find_all(&self)->Vec<Reservation>{
+    async fn find_all(&self,owner:Option<ObjectId>,filter:ReservationFilterModel,pagination:&Pagination)->RepoResult<Page<Reservation>>{
         let col = self.mongo.database.collection::<Reservation>("reservations");
-        let mut  cursor = col.find(doc! {}).await.unwrap();
-        let mut results :Vec<Reservation>=Vec::new();
-        while let Some(result) = cursor.next().await {
-           if result.is_ok(){
-            results.push(result.unwrap());
-           }
-            
+        let mut query = doc! {};
+        if let Some(owner) = owner {
+            query.insert("created_by", owner);
+        }
+        if let Some(status) = filter.status {
+            query.insert("reservation_status", status);
+        }
+        // The dates are stored as RFC 3339 strings, so compare them as dates on the server.
+        let mut window: Vec<Document> = Vec::new();
+        if let Some(to) = filter.to {
+            window.push(doc! {"$lt":[{"$dateFromString":{"dateString":"$reservation_start_date"}},DateTime::parse_rfc3339_str(to).unwrap()]});
+        }
+        if let Some(from) = filter.from {
+            window.push(doc! {"$gt":[{"$dateFromString":{"dateString":"$reservation_end_date"}},DateTime::parse_rfc3339_str(from).unwrap()]});
+        }
+        if !window.is_empty() {
+            query.insert("$expr", doc! {"$and":window});
         }
-        results
+        let total = retry(|| col.count_documents(query.clone())).await?;
+        let results = retry(|| async {
+            col.find(query.clone())
+                .sort(doc! {pagination.sort_field.as_str():pagination.sort_order})
+                .skip(pagination.skip())
+                .limit(pagination.limit as i64)
+                .await?
+                .try_collect::<Vec<Reservation>>()
+                .await
+        }).await?;
+        Ok(Page::new(results, total, pagination))
     }
-    async fn find_one(&self,_id:ObjectId)->Option<Reservation>{
+    async fn find_one(&self,_id:ObjectId)->RepoResult<Option<Reservation>>{
         let col = self.mongo.database.collection::<Reservation>("reservations");
-        let result = col.find_one(doc! {"_id":_id}).await.unwrap();
-        result
+        retry(|| col.find_one(doc! {"_id":_id})).await
     }
-    async fn update(&self,reservation:Reservation,_id:ObjectId)->UpdateResult{
-
-        println!("{}",reservation.reservation_date.to_rfc3339_string());
-        
+    /// Only matches while the reservation is still in `status`, so an edit cannot
+    /// overwrite a transition that happened since it was read.
+    async fn update(&self,reservation:Reservation,_id:ObjectId,status:ReservationStatus)->RepoResult<UpdateResult>{
         let col = self.mongo.database.collection::<Reservation>("reservations");
-        let items = Bson::from(reservation.items.iter().map(|item| Document::from(doc! {"price":item.clone().price,"room":item.clone().room})).collect::<Vec<Document>>());
-        let result = col.update_one(doc! {"_id":_id}, doc!{"$set":doc!{
+        let items = Bson::from(reservation.items.iter().map(|item| doc! {
+            "room":item.clone().room,
+            "price":item.price,
+            "unit":item.unit.to_string(),
+            "quantity":item.quantity,
+            "amount":item.amount
+        }).collect::<Vec<Document>>());
+        let update = doc!{"$set":doc!{
             "reservation_name":reservation.reservation_name,
             "description":reservation.description,
-            "reservation_date":reservation.reservation_date.to_rfc3339_string(),
-            "reservation_status":reservation.reservation_status,
-            "reservation_start_date":reservation.reservation_start_date.to_rfc3339_string(),
-            "reservation_end_date":reservation.reservation_end_date.to_rfc3339_string(),
-            "items":items
-        }}).await.unwrap();
-        result
+            "reservation_date":rfc3339(reservation.reservation_date)?,
+            "reservation_start_date":rfc3339(reservation.reservation_start_date)?,
+            "reservation_end_date":rfc3339(reservation.reservation_end_date)?,
+            "items":items,
+            "total_price":reservation.total_price
+        }};
+        let filter = doc! {"_id":_id,"reservation_status":status.to_string()};
+        retry(|| col.update_one(filter.clone(), update.clone())).await
     }
-    async fn delete(&self,_id:ObjectId)->DeleteResult{
+    async fn delete(&self,_id:ObjectId)->RepoResult<DeleteResult>{
         let col = self.mongo.database.collection::<Reservation>("reservations");
-        let result = col.delete_one(doc! {"_id":_id}).await.unwrap();
-        result
+        retry(|| col.delete_one(doc! {"_id":_id})).await
+    }
+    async fn find_active_by_rooms(&self,rooms:Vec<String>)->RepoResult<Vec<Reservation>>{
+        let col = self.mongo.database.collection::<Reservation>("reservations");
+        let filter = doc! {
+            "items.room":{"$in":rooms},
+            "reservation_status":{"$in":ReservationStatus::active_names()}
+        };
+        retry(|| async {
+            col.find(filter.clone())
+                .await?
+                .try_collect::<Vec<Reservation>>()
+                .await
+        }).await
     }
-    async fn find_all_by_user(&self,user_id:ObjectId)->Vec<Reservation>{
+    async fn update_status(&self,_id:ObjectId,from:ReservationStatus,to:ReservationStatus,cancel_reason:Option<String>)->RepoResult<UpdateResult>{
         let col = self.mongo.database.collection::<Reservation>("reservations");
-        let mut cursor = col.find(doc! {"created_by":user_id}).await.unwrap();
-        let mut results :Vec<Reservation>=Vec::new();
-        while let Some(result) = cursor.next().await {
-            if result.is_ok(){
-             results.push(result.unwrap());
-            }
-             
-         }
-         results
+        // Matching on the current status makes concurrent transitions of the same reservation fail instead of overwriting each other.
+        // Not retried: a retry after a lost reply would find the new status and report a conflict.
+        Ok(col.update_one(doc! {"_id":_id,"reservation_status":from.to_string()}, doc!{"$set":doc!{
+            "reservation_status":to.to_string(),
+            "cancel_reason":cancel_reason
+        }}).await?)
     }
     
 }
\ No newline at end of file
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use labranet_common::repository::{retry, RepoError, RepoResult};
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use rocket::async_trait;
+use rocket::tokio::time::sleep;
+
+use crate::{db::db::MongoDB, entities::room_locks::RoomLock};
+
+const LOCK_LEASE_MILLIS: i64 = 10_000;
+const LOCK_RETRY_ATTEMPTS: u32 = 20;
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Short-lived per-room locks that serialize the overlap check and the write
+/// of a reservation, so two concurrent requests cannot book the same room.
+/// A lock expires after its lease, so a crashed request never blocks a room.
+#[async_trait]
+pub trait RoomLockRepoTrait: Send + Sync {
+    async fn acquire(&self, rooms: Vec<String>, owner: ObjectId) -> RepoResult<bool>;
+    /// Extends the lease of every room `owner` still holds; false when one of the
+    /// locks has expired and may have been taken over in the meantime.
+    async fn renew(&self, rooms: Vec<String>, owner: ObjectId) -> RepoResult<bool>;
+    async fn release(&self, rooms: Vec<String>, owner: ObjectId) -> RepoResult<()>;
+}
+
+pub struct RoomLockRepo {
+    mongo: MongoDB,
+}
+impl RoomLockRepo {
+    pub fn new(mongo: MongoDB) -> Self {
+        RoomLockRepo { mongo }
+    }
+    async fn try_lock(&self, room: String, owner: ObjectId) -> RepoResult<bool> {
+        let col = self.mongo.database.collection::<RoomLock>("room_locks");
+        let now = DateTime::now();
+        let locked_until = DateTime::from_millis(now.timestamp_millis() + LOCK_LEASE_MILLIS);
+        let lock = RoomLock {
+            _id: room.clone(),
+            owner,
+            locked_until,
+        };
+        match col.insert_one(&lock).await.map_err(RepoError::from) {
+            Ok(_) => Ok(true),
+            // Take over the lock only when the previous holder's lease has run out.
+            Err(RepoError::Duplicate(_)) => Ok(col
+                .find_one_and_update(
+                    doc! {"_id":room,"locked_until":{"$lt":now}},
+                    doc! {"$set":{"owner":owner,"locked_until":locked_until}},
+                )
+                .await?
+                .is_some()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[async_trait]
+impl RoomLockRepoTrait for RoomLockRepo {
+    async fn acquire(&self, mut rooms: Vec<String>, owner: ObjectId) -> RepoResult<bool> {
+        // Always lock in the same order so two requests sharing rooms cannot deadlock.
+        rooms.sort();
+        rooms.dedup();
+        for _ in 0..LOCK_RETRY_ATTEMPTS {
+            let mut locked: Vec<String> = Vec::new();
+            for room in rooms.iter() {
+                match self.try_lock(room.clone(), owner).await? {
+                    true => locked.push(room.clone()),
+                    false => break,
+                }
+            }
+            if locked.len() == rooms.len() {
+                return Ok(true);
+            }
+            self.release(locked, owner).await?;
+            sleep(LOCK_RETRY_DELAY).await;
+        }
+        Ok(false)
+    }
+    async fn renew(&self, mut rooms: Vec<String>, owner: ObjectId) -> RepoResult<bool> {
+        rooms.sort();
+        rooms.dedup();
+        let col = self.mongo.database.collection::<RoomLock>("room_locks");
+        let now = DateTime::now();
+        let locked_until = DateTime::from_millis(now.timestamp_millis() + LOCK_LEASE_MILLIS);
+        let filter = doc! {"_id":{"$in":rooms.clone()},"owner":owner,"locked_until":{"$gte":now}};
+        let update = doc! {"$set":{"locked_until":locked_until}};
+        let result = retry(|| col.update_many(filter.clone(), update.clone())).await?;
+        Ok(result.matched_count == rooms.len() as u64)
+    }
+    async fn release(&self, rooms: Vec<String>, owner: ObjectId) -> RepoResult<()> {
+        let col = self.mongo.database.collection::<RoomLock>("room_locks");
+        retry(|| col.delete_many(doc! {"_id":{"$in":rooms.clone()},"owner":owner})).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use labranet_common::repository::{retry, RepoResult};
+use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::results::UpdateResult;
+use rocket::async_trait;
+
+use crate::{db::db::MongoDB, entities::rooms::Room};
+
+#[async_trait]
+pub trait RoomRepoTrait: Send + Sync {
+    async fn save(&self, room: Room) -> RepoResult<UpdateResult>;
+    async fn find_one(&self, _id: ObjectId) -> RepoResult<Option<Room>>;
+    async fn mark_deleted(&self, _id: ObjectId, version: i64) -> RepoResult<UpdateResult>;
+}
+
+pub struct RoomRepo {
+    mongo: MongoDB,
+}
+impl RoomRepo {
+    pub fn new(mongo: MongoDB) -> Self {
+        RoomRepo { mongo }
+    }
+}
+#[async_trait]
+impl RoomRepoTrait for RoomRepo {
+    /// Upserts the room unless it has been deleted or already holds a newer version.
+    /// Such a room is not matched by the filter, so the upsert tries to insert the
+    /// same `_id` again and fails with `RepoError::Duplicate`.
+    async fn save(&self, room: Room) -> RepoResult<UpdateResult> {
+        let col = self.mongo.database.collection::<Room>("rooms");
+        let update = doc! {
+            "$set":{
+                "floor_id":room.floor_id,
+                "room_number":room.room_number,
+                "name":room.name,
+                "price":room.price,
+                "version":room.version
+            },
+            "$setOnInsert":{"is_deleted":false}
+        };
+        retry(|| {
+            col.update_one(
+                doc! {"_id":room._id,"is_deleted":{"$ne":true},"version":{"$not":{"$gte":room.version}}},
+                update.clone(),
+            )
+            .upsert(true)
+        })
+        .await
+    }
+    async fn find_one(&self, _id: ObjectId) -> RepoResult<Option<Room>> {
+        let col = self.mongo.database.collection::<Room>("rooms");
+        retry(|| col.find_one(doc! {"_id":_id})).await
+    }
+    async fn mark_deleted(&self, _id: ObjectId, version: i64) -> RepoResult<UpdateResult> {
+        let col = self.mongo.database.collection::<Room>("rooms");
+        // Upserted so a delete that arrives before the create still leaves a tombstone.
+        retry(|| {
+            col.update_one(
+                doc! {"_id":_id},
+                doc! {"$set":{"is_deleted":true},"$max":{"version":version}},
+            )
+            .upsert(true)
+        })
+        .await
+    }
+}
@@ -1 +1,2 @@
-pub mod reservations;
\ No newline at end of file
+pub mod reservations;
+pub mod rooms;
\ No newline at end of file
@@ -1,568 +1,1312 @@
 use labranet_common::{
-    jwt::JWT,
-    response::{Response, ResponseBody, ResponseError, ResponseErrorBody},
-    roles::Role,
+    error::AppError,
+    events::{
+        reservations::{
+            ReservationCancelledEvent, ReservationCompletedEvent, ReservationCreatedEvent,
+            ReservationDeletedEvent, ReservationUpdatedEvent,
+        },
+        Event, Publisher,
+    },
+    jwt::{Authorized, Claims, JWT},
+    pagination::{Page, PageQuery},
+    permissions::{
+        ReservationsRead, ReservationsWrite, RESERVATIONS_READ_ANY, RESERVATIONS_STATUS,
+        RESERVATIONS_WRITE_ANY,
+    },
+    response::{Response, ResponseBody},
+    reservation_status::ReservationStatus,
+    validation::{field_error, validate_with, ValidationErrors},
 };
-use mongodb::bson::oid::ObjectId;
-use rocket::{
-    async_trait,
-    http::Status,
-    response::status::{Created, Custom},
-};
-use std::{result, str::FromStr};
+use mongodb::bson::{oid::ObjectId, to_document, DateTime};
+use rocket::{async_trait, response::status::Created};
 
 use crate::{
-    entities::reservations::{Reservation, ReservationItem},
-    models::reservations::{ReservationModel, ReservationStatus},
-    repositories::reservations::ReservationRepoTrait,
+    entities::{
+        reservation_histories::{ReservationAction, ReservationFieldChange, ReservationHistory},
+        reservations::{PriceUnit, Reservation, ReservationItem},
+        rooms::Room,
+    },
+    models::reservations::{ReservationConflictModel, ReservationFilterModel, ReservationModel},
+    repositories::{
+        reservation_histories::ReservationHistoryRepoTrait, reservations::ReservationRepoTrait,
+        room_locks::RoomLockRepoTrait, rooms::RoomRepoTrait,
+    },
+    views::{reservation_histories::ReservationHistoryView, reservations::ReservationView},
 };
 
+const HOUR_MILLIS: i64 = 60 * 60 * 1000;
+const NIGHT_MILLIS: i64 = 24 * HOUR_MILLIS;
+const HOURS_PER_NIGHT: f64 = 24.0;
+const RESERVATION_SORT_FIELDS: [&str; 5] = [
+    "reservation_name",
+    "reservation_date",
+    "reservation_start_date",
+    "reservation_end_date",
+    "total_price",
+];
+
+fn round_price(price: f64) -> f64 {
+    (price * 100.0).round() / 100.0
+}
+fn total_price(items: &[ReservationItem]) -> f64 {
+    round_price(items.iter().map(|item| item.amount).sum())
+}
+/// The form a room id is stored, locked and queried in. `ObjectId::parse_str` also
+/// accepts uppercase hex, so the id a client sent cannot be used as is.
+fn room_key(room: &str) -> Option<String> {
+    ObjectId::parse_str(room).ok().map(|room_id| room_id.to_hex())
+}
+
 #[async_trait]
 pub trait ReservationUseCaseTrait: Send + Sync {
     async fn new_reservation(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<ReservationsWrite>, AppError>,
         model: ReservationModel,
-    ) -> Result<Created<String>, Custom<String>>;
+    ) -> Result<Created<Response<ReservationView>>, AppError>;
     async fn fetch_all_reservation(
         &self,
-        key: Result<JWT, ResponseError<String>>,
-    ) -> Result<String, Custom<String>>;
+        key: Result<Authorized<ReservationsRead>, AppError>,
+        filter: ReservationFilterModel,
+        query: PageQuery,
+    ) -> Result<Response<Page<ReservationView>>, AppError>;
     async fn fetch_one_reservation(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<ReservationsRead>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
+    ) -> Result<Response<ReservationView>, AppError>;
     async fn update_reservation(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<ReservationsWrite>, AppError>,
         model: ReservationModel,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
+    ) -> Result<Response<ReservationView>, AppError>;
     async fn delete_reservation(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<ReservationsWrite>, AppError>,
+        _id: &str,
+    ) -> Result<Response<String>, AppError>;
+    async fn transition_reservation(
+        &self,
+        key: Result<JWT, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
+        status: ReservationStatus,
+        cancel_reason: Option<String>,
+    ) -> Result<Response<ReservationView>, AppError>;
+    async fn fetch_reservation_history(
+        &self,
+        key: Result<Authorized<ReservationsRead>, AppError>,
+        _id: &str,
+    ) -> Result<Response<Vec<ReservationHistoryView>>, AppError>;
 }
 
 pub struct ReservationUseCase {
     pub repo: Box<dyn ReservationRepoTrait>,
+    pub lock_repo: Box<dyn RoomLockRepoTrait>,
+    pub room_repo: Box<dyn RoomRepoTrait>,
+    pub history_repo: Box<dyn ReservationHistoryRepoTrait>,
+    pub publisher: Publisher,
 }
 impl ReservationUseCase {
-    pub fn new(repo: Box<dyn ReservationRepoTrait>) -> Self {
-        ReservationUseCase { repo }
-    }
-    fn validate_reservation(&self, model: ReservationModel) -> Vec<String> {
-        let errors = [
-            match model.reservation_name == "" {
-                true => Some("Reservation name is required".to_string()),
-                false => None,
-            },
-            match model.description == "" {
-                true => Some("Description is required".to_string()),
-                false => None,
-            },
-            match model.items.is_empty() {
-                true => Some("Reservation Item is required".to_string()),
-                false => None,
-            },
-        ]
-        .to_vec();
-        errors
+    pub fn new(
+        repo: Box<dyn ReservationRepoTrait>,
+        lock_repo: Box<dyn RoomLockRepoTrait>,
+        room_repo: Box<dyn RoomRepoTrait>,
+        history_repo: Box<dyn ReservationHistoryRepoTrait>,
+        publisher: Publisher,
+    ) -> Self {
+        ReservationUseCase {
+            repo,
+            lock_repo,
+            room_repo,
+            history_repo,
+            publisher,
+        }
+    }
+    async fn publish<E: Event>(&self, event: E) {
+        if let Err(err) = self.publisher.publish(&event).await {
+            println!("Cannot publish {}: {}", E::SUBJECT, err);
+        }
+    }
+    fn rooms_of(&self, reservation: &Reservation) -> Vec<String> {
+        let mut rooms = reservation
+            .items
             .iter()
-            .map(|error| error.to_owned().to_owned())
-            .flatten()
-            .collect::<Vec<String>>()
+            .map(|item| item.room.clone())
+            .collect::<Vec<String>>();
+        rooms.sort();
+        rooms.dedup();
+        rooms
     }
-}
-#[async_trait]
-impl ReservationUseCaseTrait for ReservationUseCase {
-    async fn new_reservation(
-        &self,
-        key: Result<JWT, ResponseError<String>>,
-        model: ReservationModel,
-    ) -> Result<Created<String>, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let errors = self.validate_reservation(model.clone());
-                match errors.len() > 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let reservation = Reservation {
-                            _id: ObjectId::new(),
-                            reservation_name: model.reservation_name,
-                            description: model.description,
-                            reservation_date: model.reservation_date,
-                            reservation_status: ReservationStatus::Save.to_string(),
-                            reservation_end_date: model.reservation_start_date,
-                            reservation_start_date: model.reservation_end_date,
-                            items: model
-                                .items
-                                .iter()
-                                .map(|item| ReservationItem {
-                                    price: item.clone().price,
-                                    room: item.clone().room,
-                                })
-                                .collect::<Vec<ReservationItem>>(),
-                            created_by: ObjectId::from_str(_k.claims.subject_id.as_str()).unwrap(),
-                        };
-                        let insert_result = self.repo.add(reservation).await;
-                        let result = self
-                            .repo
-                            .find_one(insert_result.inserted_id.as_object_id().unwrap())
-                            .await
-                            .unwrap();
-                        let response = Response {
-                            body: ResponseBody::<Reservation>::Data(result),
-                        };
-                        Ok(Created::new("").tagged_body(serde_json::to_string(&response).unwrap()))
-                    }
-                }
+    fn created_event(&self, reservation: &Reservation) -> ReservationCreatedEvent {
+        ReservationCreatedEvent {
+            id: reservation._id.to_hex(),
+            rooms: self.rooms_of(reservation),
+            reservation_start_date: reservation
+                .reservation_start_date
+                .try_to_rfc3339_string()
+                .unwrap(),
+            reservation_end_date: reservation
+                .reservation_end_date
+                .try_to_rfc3339_string()
+                .unwrap(),
+            status: reservation.reservation_status.clone(),
+            version: DateTime::now().timestamp_millis(),
+        }
+    }
+    fn updated_event(&self, reservation: &Reservation) -> ReservationUpdatedEvent {
+        let event = self.created_event(reservation);
+        ReservationUpdatedEvent {
+            id: event.id,
+            rooms: event.rooms,
+            reservation_start_date: event.reservation_start_date,
+            reservation_end_date: event.reservation_end_date,
+            status: event.status,
+            version: event.version,
+        }
+    }
+    async fn publish_status_change(&self, reservation: &Reservation) {
+        let id = reservation._id.to_hex();
+        let version = DateTime::now().timestamp_millis();
+        match reservation.reservation_status {
+            ReservationStatus::Cancel => {
+                self.publish(ReservationCancelledEvent { id, version }).await
             }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
+            ReservationStatus::Complete => {
+                self.publish(ReservationCompletedEvent { id, version }).await
             }
+            _ => self.publish(self.updated_event(reservation)).await,
         }
     }
-
-    async fn fetch_all_reservation(
+    /// Checks what the derived rules cannot: the dates are in order and every item
+    /// names a live room at the price the service charges for it.
+    async fn validate_reservation(
         &self,
-        key: Result<JWT, ResponseError<String>>,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => match _k.claims.subject_role == Role::Admin.to_string() {
-                true => {
-                    let results = self.repo.find_all().await;
-                    match results.len() == 0 {
-                        true => {
-                            let response = ResponseError {
-                                error: ResponseErrorBody::<String>::Error(
-                                    "Not Found Reservations".to_string(),
-                                ),
-                            };
-                            Err(Custom(
-                                Status {
-                                    code: Status::NotFound.code,
-                                },
-                                serde_json::to_string(&response).unwrap(),
-                            ))
-                        }
-                        false => {
-                            let response = Response {
-                                body: ResponseBody::<Vec<Reservation>>::Data(results),
-                            };
-                            Ok(serde_json::to_string(&response).unwrap())
-                        }
-                    }
-                }
-                false => {
-                    let results = self
-                        .repo
-                        .find_all_by_user(ObjectId::parse_str(_k.claims.subject_id).unwrap())
-                        .await;
-                    match results.len() == 0 {
-                        true => {
-                            let response = ResponseError {
-                                error: ResponseErrorBody::<String>::Error(
-                                    "Not Found Reservations".to_string(),
-                                ),
-                            };
-                            Err(Custom(
-                                Status {
-                                    code: Status::NotFound.code,
-                                },
-                                serde_json::to_string(&response).unwrap(),
-                            ))
-                        }
-                        false => {
-                            let response = Response {
-                                body: ResponseBody::<Vec<Reservation>>::Data(results),
-                            };
-                            Ok(serde_json::to_string(&response).unwrap())
-                        }
-                    }
-                }
-            },
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
+        model: &ReservationModel,
+    ) -> Result<ValidationErrors, AppError> {
+        let mut errors = ValidationErrors::new();
+        if model.reservation_start_date >= model.reservation_end_date {
+            errors.add(
+                "reservation_end_date",
+                field_error(
+                    "date_order",
+                    "Reservation start date must be before end date",
+                ),
+            );
+        }
+        if errors.is_empty() {
+            for error in self.validate_rooms(model).await? {
+                errors.add("items", field_error("room", error));
             }
         }
+        Ok(errors)
     }
-    async fn fetch_one_reservation(
-        &self,
-        key: Result<JWT, ResponseError<String>>,
-        _id: &str,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let id = ObjectId::parse_str(_id);
-                match id.clone().is_err() {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error("Invalid id".to_string()),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
+
+    /// The rules of `GET /reservations` that span two query parameters.
+    fn validate_reservation_filter(&self, filter: &ReservationFilterModel) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        let from = filter.from.as_deref().map(DateTime::parse_rfc3339_str);
+        let to = filter.to.as_deref().map(DateTime::parse_rfc3339_str);
+        if let (Some(Ok(from)), Some(Ok(to))) = (from, to) {
+            if from >= to {
+                errors.add("to", field_error("date_order", "From must be before to"));
+            }
+        }
+        errors
+    }
+
+    /// Prices one room over the reservation window. Stays of a day or more are
+    /// charged per started night at the room price, shorter stays per started hour
+    /// at a 24th of it.
+    fn price_item(&self, room: &Room, model: &ReservationModel) -> ReservationItem {
+        let duration = model.reservation_end_date.timestamp_millis()
+            - model.reservation_start_date.timestamp_millis();
+        let (unit, quantity, price) = match duration >= NIGHT_MILLIS {
+            true => (
+                PriceUnit::Night,
+                (duration + NIGHT_MILLIS - 1) / NIGHT_MILLIS,
+                room.price,
+            ),
+            false => (
+                PriceUnit::Hour,
+                (duration + HOUR_MILLIS - 1) / HOUR_MILLIS,
+                round_price(room.price / HOURS_PER_NIGHT),
+            ),
+        };
+        ReservationItem {
+            room: room._id.to_hex(),
+            price,
+            unit,
+            quantity,
+            amount: round_price(price * quantity as f64),
+        }
+    }
+
+    async fn validate_rooms(&self, model: &ReservationModel) -> Result<Vec<String>, AppError> {
+        let mut errors: Vec<String> = Vec::new();
+        let mut seen: Vec<ObjectId> = Vec::new();
+        for item in model.items.iter() {
+            match ObjectId::parse_str(&item.room) {
+                Err(_) => errors.push(format!("Room {} is invalid", item.room)),
+                Ok(room_id) if seen.contains(&room_id) => {
+                    errors.push(format!("Room {} is listed more than once", item.room))
+                }
+                Ok(room_id) => match self.room_repo.find_one(room_id).await? {
+                    None => errors.push(format!("Room {} does not exist", item.room)),
+                    Some(room) if room.is_deleted => {
+                        errors.push(format!("Room {} has been deleted", item.room))
                     }
-                    false => {
-                        println!("{}", id.clone().unwrap());
-                        let result = self.repo.find_one(id.clone().unwrap()).await;
-                        match result {
-                            Some(res) => match _k.claims.subject_role == Role::Worker.to_string() {
-                                true => {
-                                    match res.created_by
-                                        == ObjectId::from_str(_k.claims.subject_id.as_str())
-                                            .unwrap()
-                                    {
-                                        true => {
-                                            let response = Response {
-                                                body: ResponseBody::<Reservation>::Data(res),
-                                            };
-                                            Ok(serde_json::to_string(&response).unwrap())
-                                        }
-                                        false => {
-                                            let response = ResponseError {
-                                                error: ResponseErrorBody::<String>::Error(
-                                                    "Not Authorize Reservations".to_string(),
-                                                ),
-                                            };
-                                            Err(Custom(
-                                                Status {
-                                                    code: Status::Unauthorized.code,
-                                                },
-                                                serde_json::to_string(&response).unwrap(),
-                                            ))
-                                        }
-                                    }
-                                }
-                                false => {
-                                    let response = Response {
-                                        body: ResponseBody::<Reservation>::Data(res),
-                                    };
-                                    Ok(serde_json::to_string(&response).unwrap())
-                                }
-                            },
-                            None => {
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Not Found Reservations".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::NotFound.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
+                    Some(room) => {
+                        seen.push(room_id);
+                        let priced = self.price_item(&room, model);
+                        if let Some(price) = item.price {
+                            if (price - priced.price).abs() >= 0.005 {
+                                errors.push(format!(
+                                    "Price of room {} must be {} per {}",
+                                    item.room, priced.price, priced.unit
                                 ))
                             }
                         }
                     }
+                },
+            }
+        }
+        Ok(errors)
+    }
+
+    /// Prices every room of `model`. The rooms were checked by `validate_rooms`, but
+    /// one may have been deleted since, which is reported as a conflict.
+    async fn price_items(
+        &self,
+        model: &ReservationModel,
+    ) -> Result<Vec<ReservationItem>, AppError> {
+        let mut items: Vec<ReservationItem> = Vec::new();
+        for item in model.items.iter() {
+            let room_id = ObjectId::parse_str(&item.room)
+                .map_err(|_| AppError::Validation(format!("Room {} is invalid", item.room)))?;
+            match self.room_repo.find_one(room_id).await? {
+                None => {
+                    return Err(AppError::Validation(format!(
+                        "Room {} does not exist",
+                        item.room
+                    )))
                 }
+                Some(room) if room.is_deleted => {
+                    return Err(AppError::conflict(format!(
+                        "Room {} has been deleted",
+                        item.room
+                    )))
+                }
+                Some(room) => items.push(self.price_item(&room, model)),
+            }
+        }
+        Ok(items)
+    }
+
+    fn find_conflicts(
+        &self,
+        reservations: Vec<Reservation>,
+        model: &ReservationModel,
+        exclude_id: Option<ObjectId>,
+    ) -> Vec<ReservationConflictModel> {
+        let rooms = model
+            .items
+            .iter()
+            .filter_map(|item| room_key(&item.room))
+            .collect::<Vec<String>>();
+        reservations
+            .iter()
+            .filter(|reservation| Some(reservation._id) != exclude_id)
+            .filter(|reservation| {
+                reservation.reservation_start_date < model.reservation_end_date
+                    && model.reservation_start_date < reservation.reservation_end_date
+            })
+            .flat_map(|reservation| {
+                reservation
+                    .items
+                    .iter()
+                    .filter_map(|item| room_key(&item.room))
+                    .filter(|room| rooms.contains(room))
+                    .map(|room| ReservationConflictModel {
+                        reservation_id: reservation._id.to_hex(),
+                        room,
+                        reservation_start_date: reservation.reservation_start_date,
+                        reservation_end_date: reservation.reservation_end_date,
+                    })
+            })
+            .collect::<Vec<ReservationConflictModel>>()
+    }
+
+    /// Locks every room of the model and checks it against the existing bookings.
+    /// On success the caller must write the reservation and then release the
+    /// returned rooms with the returned owner; on failure nothing stays locked.
+    async fn lock_rooms(
+        &self,
+        model: &ReservationModel,
+        exclude_id: Option<ObjectId>,
+    ) -> Result<(Vec<String>, ObjectId), AppError> {
+        let owner = ObjectId::new();
+        let mut rooms: Vec<String> = Vec::new();
+        for item in model.items.iter() {
+            let room = room_key(&item.room)
+                .ok_or_else(|| AppError::Validation(format!("Room {} is invalid", item.room)))?;
+            rooms.push(room);
+        }
+        rooms.sort();
+        rooms.dedup();
+        if !self.lock_repo.acquire(rooms.clone(), owner).await? {
+            return Err(AppError::conflict(
+                "Room is being reserved by another request",
+            ));
+        }
+        let reservations = match self.repo.find_active_by_rooms(rooms.clone()).await {
+            Ok(reservations) => reservations,
+            Err(err) => {
+                self.lock_repo.release(rooms, owner).await?;
+                return Err(err.into());
             }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
+        };
+        let conflicts = self.find_conflicts(reservations, model, exclude_id);
+        if !conflicts.is_empty() {
+            self.lock_repo.release(rooms, owner).await?;
+            return Err(AppError::Conflict {
+                message: "Room is already reserved".to_string(),
+                details: serde_json::to_value(conflicts).ok(),
+            });
+        }
+        Ok((rooms, owner))
+    }
+
+    /// Called right before the write: the checks of `lock_rooms` only hold while the
+    /// rooms are still ours, and a slow request may have outlived the lease.
+    async fn keep_rooms(&self, rooms: &[String], owner: ObjectId) -> Result<(), AppError> {
+        if self.lock_repo.renew(rooms.to_vec(), owner).await? {
+            return Ok(());
+        }
+        self.lock_repo.release(rooms.to_vec(), owner).await?;
+        Err(AppError::conflict(
+            "Room is being reserved by another request",
+        ))
+    }
+
+    /// Callers without `any_permission` may only touch the reservations they created.
+    fn check_owner(
+        &self,
+        claims: &Claims,
+        any_permission: &str,
+        reservation: &Reservation,
+    ) -> Result<(), AppError> {
+        match claims.has_permission(any_permission)
+            || ObjectId::parse_str(&claims.subject_id).ok() == Some(reservation.created_by)
+        {
+            true => Ok(()),
+            false => Err(AppError::Forbidden(
+                "Not Authorize Reservations".to_string(),
+            )),
+        }
+    }
+
+    /// Confirming, completing and marking a no-show need `RESERVATIONS_STATUS`; a
+    /// reservation is cancelled by its owner or by a caller who may change any.
+    fn can_trigger(
+        &self,
+        claims: &Claims,
+        reservation: &Reservation,
+        next: &ReservationStatus,
+    ) -> bool {
+        match next {
+            ReservationStatus::Cancel => {
+                claims.has_permission(RESERVATIONS_WRITE_ANY)
+                    || ObjectId::parse_str(&claims.subject_id).ok()
+                        == Some(reservation.created_by)
             }
+            _ => claims.has_permission(RESERVATIONS_STATUS),
         }
     }
+
+    /// Lists every stored field whose value differs between the two versions of a
+    /// reservation; a missing version stands for "did not exist".
+    fn diff_reservations(
+        &self,
+        before: Option<&Reservation>,
+        after: Option<&Reservation>,
+    ) -> Vec<ReservationFieldChange> {
+        let before = before
+            .map(|reservation| to_document(reservation).unwrap())
+            .unwrap_or_default();
+        let after = after
+            .map(|reservation| to_document(reservation).unwrap())
+            .unwrap_or_default();
+        let mut fields = before
+            .keys()
+            .chain(after.keys())
+            .filter(|field| *field != "_id")
+            .cloned()
+            .collect::<Vec<String>>();
+        fields.sort();
+        fields.dedup();
+        fields
+            .into_iter()
+            .filter(|field| before.get(field) != after.get(field))
+            .map(|field| ReservationFieldChange {
+                before: before.get(&field).cloned(),
+                after: after.get(&field).cloned(),
+                field,
+            })
+            .collect::<Vec<ReservationFieldChange>>()
+    }
+
+    /// The caller's user id. Read before any write, so a token with a malformed
+    /// subject changes nothing.
+    fn actor_id(&self, claims: &Claims) -> Result<ObjectId, AppError> {
+        ObjectId::parse_str(&claims.subject_id)
+            .map_err(|_| AppError::Unauthorized("Invalid token subject".to_string()))
+    }
+
+    /// Adds the history entry of a change that is already written. The standalone
+    /// Mongo the service runs on has no transactions, so the insert is retried by the
+    /// repository and a failure is only logged: the change stands and its event must
+    /// still be published.
+    async fn record_history(
+        &self,
+        actor_id: ObjectId,
+        action: ReservationAction,
+        before: Option<&Reservation>,
+        after: Option<&Reservation>,
+    ) {
+        let reservation_id = match after.or(before) {
+            Some(reservation) => reservation._id,
+            None => return,
+        };
+        let history = ReservationHistory {
+            _id: ObjectId::new(),
+            reservation_id,
+            actor_id,
+            action: action.clone(),
+            created_at: DateTime::now(),
+            changes: self.diff_reservations(before, after),
+        };
+        if let Err(err) = self.history_repo.add(history).await {
+            println!(
+                "Cannot record {:?} history of reservation {}: {}",
+                action, reservation_id, err
+            );
+        }
+    }
+
+    async fn save_reservation(
+        &self,
+        reservation_db: Reservation,
+        model: ReservationModel,
+        actor_id: ObjectId,
+    ) -> Result<Response<ReservationView>, AppError> {
+        if reservation_db.reservation_status.is_final() {
+            return Err(AppError::conflict(format!(
+                "Reservation in status {} cannot be changed",
+                reservation_db.reservation_status
+            )));
+        }
+        let items = self.price_items(&model).await?;
+        let (rooms, owner) = self.lock_rooms(&model, Some(reservation_db._id)).await?;
+        let before = reservation_db.clone();
+        let reservation = Reservation {
+            _id: reservation_db._id,
+            reservation_name: model.reservation_name,
+            description: model.description,
+            reservation_date: model.reservation_date,
+            reservation_status: reservation_db.reservation_status,
+            cancel_reason: reservation_db.cancel_reason,
+            reservation_start_date: model.reservation_start_date,
+            reservation_end_date: model.reservation_end_date,
+            total_price: total_price(&items),
+            items,
+            created_by: reservation_db.created_by,
+        };
+        self.keep_rooms(&rooms, owner).await?;
+        // Release the rooms whether or not the write went through.
+        let update_result = self
+            .repo
+            .update(
+                reservation,
+                reservation_db._id,
+                before.reservation_status.clone(),
+            )
+            .await;
+        self.lock_repo.release(rooms, owner).await?;
+        if update_result?.matched_count == 0 {
+            return Err(AppError::conflict(
+                "Reservation was changed by another request",
+            ));
+        }
+        let result = self
+            .repo
+            .find_one(reservation_db._id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Reservations".to_string()))?;
+        self.record_history(
+            actor_id,
+            ReservationAction::Update,
+            Some(&before),
+            Some(&result),
+        )
+        .await;
+        self.publish(self.updated_event(&result)).await;
+        Ok(Response {
+            body: ResponseBody::<ReservationView>::Data(result.into()),
+        })
+    }
+}
+#[async_trait]
+impl ReservationUseCaseTrait for ReservationUseCase {
+    async fn new_reservation(
+        &self,
+        key: Result<Authorized<ReservationsWrite>, AppError>,
+        model: ReservationModel,
+    ) -> Result<Created<Response<ReservationView>>, AppError> {
+        let _k = key?;
+        validate_with(&model, self.validate_reservation(&model).await?)?;
+        let created_by = self.actor_id(&_k.jwt.claims)?;
+        let items = self.price_items(&model).await?;
+        let (rooms, owner) = self.lock_rooms(&model, None).await?;
+        let reservation = Reservation {
+            _id: ObjectId::new(),
+            reservation_name: model.reservation_name,
+            description: model.description,
+            reservation_date: model.reservation_date,
+            reservation_status: ReservationStatus::Save,
+            cancel_reason: None,
+            reservation_start_date: model.reservation_start_date,
+            reservation_end_date: model.reservation_end_date,
+            total_price: total_price(&items),
+            items,
+            created_by,
+        };
+        self.keep_rooms(&rooms, owner).await?;
+        let insert_result = self.repo.add(reservation).await;
+        self.lock_repo.release(rooms, owner).await?;
+        let result = self
+            .repo
+            .find_one(insert_result?.inserted_id.as_object_id().unwrap())
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Reservations".to_string()))?;
+        self.record_history(
+            created_by,
+            ReservationAction::Create,
+            None,
+            Some(&result),
+        )
+        .await;
+        self.publish(self.created_event(&result)).await;
+        let response = Response {
+            body: ResponseBody::<ReservationView>::Data(result.into()),
+        };
+        Ok(Created::new("").body(response))
+    }
+
+    async fn fetch_all_reservation(
+        &self,
+        key: Result<Authorized<ReservationsRead>, AppError>,
+        filter: ReservationFilterModel,
+        query: PageQuery,
+    ) -> Result<Response<Page<ReservationView>>, AppError> {
+        let claims = key?.jwt.claims;
+        validate_with(&filter, self.validate_reservation_filter(&filter))?;
+        let pagination = query.validate(&RESERVATION_SORT_FIELDS)?;
+        // Callers who may only read their own reservations only ever see those.
+        let owner = match claims.has_permission(RESERVATIONS_READ_ANY) {
+            true => None,
+            false => Some(self.actor_id(&claims)?),
+        };
+        let results = self.repo.find_all(owner, filter, &pagination).await?;
+        Ok(Response {
+            body: ResponseBody::<Page<ReservationView>>::Data(results.map(ReservationView::from)),
+        })
+    }
+    async fn fetch_one_reservation(
+        &self,
+        key: Result<Authorized<ReservationsRead>, AppError>,
+        _id: &str,
+    ) -> Result<Response<ReservationView>, AppError> {
+        let _k = key?;
+        let id = ObjectId::parse_str(_id)?;
+        let res = self
+            .repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Reservations".to_string()))?;
+        self.check_owner(&_k.jwt.claims, RESERVATIONS_READ_ANY, &res)?;
+        Ok(Response {
+            body: ResponseBody::<ReservationView>::Data(res.into()),
+        })
+    }
     async fn update_reservation(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<ReservationsWrite>, AppError>,
         model: ReservationModel,
         _id: &str,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let errors = self.validate_reservation(model.clone());
-                match errors.len() > 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let id = ObjectId::parse_str(_id);
-                        match id.is_err() {
-                            true => {
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Invalid id".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::BadRequest.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            }
-                            false => {
-                                println!("{}", id.clone().unwrap());
-                                let result = self.repo.find_one(id.clone().unwrap()).await;
-                                match result {
-                                    Some(reservation_db) => {
-                                        match _k.claims.subject_role == Role::Worker.to_string() {
-                                            true => match reservation_db.created_by
-                                                == ObjectId::from_str(&_k.claims.subject_id)
-                                                    .unwrap()
-                                            {
-                                                true => {
-                                                    println!("Update Id: {}", id.clone().unwrap());
-                                                    let reservation = Reservation {
-                                                        _id: reservation_db._id,
-                                                        reservation_name: model.reservation_name,
-                                                        description: model.description,
-                                                        reservation_date: model.reservation_date,
-                                                        reservation_status: reservation_db.reservation_status,
-                                                        reservation_start_date: model
-                                                            .reservation_start_date,
-                                                        reservation_end_date: model
-                                                            .reservation_end_date,
-                                                        items: model
-                                                            .items
-                                                            .iter()
-                                                            .map(|item| ReservationItem {
-                                                                price: item.clone().price,
-                                                                room: item.clone().room,
-                                                            })
-                                                            .collect::<Vec<ReservationItem>>(),
-                                                        created_by: reservation_db.created_by,
-                                                    };
-                                                    self.repo
-                                                        .update(reservation, id.clone().unwrap())
-                                                        .await;
-                                                    let result = self
-                                                        .repo
-                                                        .find_one(reservation_db._id)
-                                                        .await
-                                                        .unwrap();
-                                                    let response = Response {
-                                                        body: ResponseBody::<Reservation>::Data(
-                                                            result,
-                                                        ),
-                                                    };
-                                                    Ok(serde_json::to_string(&response).unwrap())
-                                                }
-                                                false => {
-                                                    let response = ResponseError {
-                                                        error: ResponseErrorBody::<String>::Error(
-                                                            "Not Authorize Reservations".to_string(),
-                                                        ),
-                                                    };
-                                                    Err(Custom(
-                                                        Status {
-                                                            code: Status::Unauthorized.code,
-                                                        },
-                                                        serde_json::to_string(&response).unwrap(),
-                                                    ))
-                                                }
-                                            },
-
-                                            false => {
-                                                println!("Update Id: {}", id.clone().unwrap());
-                                                let reservation = Reservation {
-                                                    _id: reservation_db._id,
-                                                    reservation_name: model.reservation_name,
-                                                    description: model.description,
-                                                    reservation_date: model.reservation_date,
-                                                    reservation_status: reservation_db.reservation_status,
-                                                    reservation_start_date: model
-                                                        .reservation_start_date,
-                                                    reservation_end_date: model
-                                                        .reservation_end_date,
-                                                    items: model
-                                                        .items
-                                                        .iter()
-                                                        .map(|item| ReservationItem {
-                                                            price: item.clone().price,
-                                                            room: item.clone().room,
-                                                        })
-                                                        .collect::<Vec<ReservationItem>>(),
-                                                    created_by: reservation_db.created_by,
-                                                };
-                                                self.repo
-                                                    .update(reservation, id.clone().unwrap())
-                                                    .await;
-                                                let result = self
-                                                    .repo
-                                                    .find_one(reservation_db._id)
-                                                    .await
-                                                    .unwrap();
-                                                let response = Response {
-                                                    body: ResponseBody::<Reservation>::Data(result),
-                                                };
-                                                Ok(serde_json::to_string(&response).unwrap())
-                                            }
-                                        }
-                                    }
-                                    None => {
-                                        let response = ResponseError {
-                                            error: ResponseErrorBody::<String>::Error(
-                                                "Not Found Reservations".to_string(),
-                                            ),
-                                        };
-                                        Err(Custom(
-                                            Status {
-                                                code: Status::NotFound.code,
-                                            },
-                                            serde_json::to_string(&response).unwrap(),
-                                        ))
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+    ) -> Result<Response<ReservationView>, AppError> {
+        let _k = key?;
+        validate_with(&model, self.validate_reservation(&model).await?)?;
+        let id = ObjectId::parse_str(_id)?;
+        let reservation_db = self
+            .repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Reservations".to_string()))?;
+        self.check_owner(&_k.jwt.claims, RESERVATIONS_WRITE_ANY, &reservation_db)?;
+        self.save_reservation(reservation_db, model, self.actor_id(&_k.jwt.claims)?)
+            .await
     }
     async fn delete_reservation(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<ReservationsWrite>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let id = ObjectId::parse_str(_id);
-                match id.is_err() {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error("Invalid id".to_string()),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        println!("{}", id.clone().unwrap());
-                        let result = self.repo.find_one(id.clone().unwrap()).await;
-                        match result {
-                            Some(res) => {
-                                 match _k.claims.subject_role==Role::Worker.to_string() {
-                                    true=>match res.created_by==ObjectId::from_str(&_k.claims.subject_id).unwrap() {
-                                        true=>{
-                                            self.repo.delete(res._id).await;
-                                            let response = Response {
-                                                body: ResponseBody::<String>::Data(
-                                                    "Delete Success".to_string(),
-                                                ),
-                                            };
-                                            Ok(serde_json::to_string(&response).unwrap())
-                                        },
-                                        false=>{
-                                            let response = ResponseError {
-                                                error: ResponseErrorBody::<String>::Error(
-                                                    "Not Authorize Reservations".to_string(),
-                                                ),
-                                            };
-                                            Err(Custom(
-                                                Status {
-                                                    code: Status::Unauthorized.code,
-                                                },
-                                                serde_json::to_string(&response).unwrap(),
-                                            ))
-                                        }
-                                        
-                                    },
-                                    false=>{
-                                        self.repo.delete(res._id).await;
-                                        let response = Response {
-                                            body: ResponseBody::<String>::Data(
-                                                "Delete Success".to_string(),
-                                            ),
-                                        };
-                                        Ok(serde_json::to_string(&response).unwrap())
-                                    }
-                                     
-                                 }
-                               
-                            }
-                            None => {
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Not Found Reservations".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::NotFound.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            }
-                        }
-                    }
-                }
+    ) -> Result<Response<String>, AppError> {
+        let _k = key?;
+        let id = ObjectId::parse_str(_id)?;
+        let res = self
+            .repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Reservations".to_string()))?;
+        self.check_owner(&_k.jwt.claims, RESERVATIONS_WRITE_ANY, &res)?;
+        let actor_id = self.actor_id(&_k.jwt.claims)?;
+        self.repo.delete(res._id).await?;
+        self.record_history(
+            actor_id,
+            ReservationAction::Delete,
+            Some(&res),
+            None,
+        )
+        .await;
+        self.publish(ReservationDeletedEvent {
+            id: res._id.to_hex(),
+            version: DateTime::now().timestamp_millis(),
+        })
+        .await;
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Delete Success".to_string()),
+        })
+    }
+    async fn transition_reservation(
+        &self,
+        key: Result<JWT, AppError>,
+        _id: &str,
+        status: ReservationStatus,
+        cancel_reason: Option<String>,
+    ) -> Result<Response<ReservationView>, AppError> {
+        let claims = key?.claims;
+        let actor_id = self.actor_id(&claims)?;
+        let id = ObjectId::parse_str(_id)?;
+        if status == ReservationStatus::Cancel
+            && cancel_reason.clone().unwrap_or_default().trim().is_empty()
+        {
+            return Err(AppError::Validation(
+                "Cancel reason is required".to_string(),
+            ));
+        }
+        let reservation = self
+            .repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Reservations".to_string()))?;
+        if !self.can_trigger(&claims, &reservation, &status) {
+            return Err(AppError::Forbidden(
+                "Not Authorize Reservations".to_string(),
+            ));
+        }
+        if !reservation.reservation_status.can_transition_to(&status) {
+            return Err(AppError::conflict(format!(
+                "Cannot change reservation from {} to {}",
+                reservation.reservation_status, status
+            )));
+        }
+        let update_result = self
+            .repo
+            .update_status(
+                id,
+                reservation.reservation_status.clone(),
+                status,
+                cancel_reason,
+            )
+            .await?;
+        if update_result.modified_count == 0 {
+            return Err(AppError::conflict(
+                "Reservation was changed by another request",
+            ));
+        }
+        let result = self
+            .repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Reservations".to_string()))?;
+        self.record_history(
+            actor_id,
+            ReservationAction::StatusChange,
+            Some(&reservation),
+            Some(&result),
+        )
+        .await;
+        self.publish_status_change(&result).await;
+        Ok(Response {
+            body: ResponseBody::<ReservationView>::Data(result.into()),
+        })
+    }
+
+    async fn fetch_reservation_history(
+        &self,
+        key: Result<Authorized<ReservationsRead>, AppError>,
+        _id: &str,
+    ) -> Result<Response<Vec<ReservationHistoryView>>, AppError> {
+        let claims = key?.jwt.claims;
+        let id = ObjectId::parse_str(_id)?;
+        let histories = self.history_repo.find_all_by_reservation(id).await?;
+        // The history outlives a deleted reservation, so fall back to whoever created it.
+        let owner = match self.repo.find_one(id).await? {
+            Some(reservation) => Some(reservation.created_by),
+            None => histories
+                .iter()
+                .find(|history| history.action == ReservationAction::Create)
+                .map(|history| history.actor_id),
+        };
+        match owner {
+            None => Err(AppError::NotFound("Not Found Reservations".to_string())),
+            Some(owner) => match claims.has_permission(RESERVATIONS_READ_ANY)
+                || ObjectId::parse_str(&claims.subject_id).ok() == Some(owner)
+            {
+                true => Ok(Response {
+                    body: ResponseBody::<Vec<ReservationHistoryView>>::Data(
+                        histories
+                            .into_iter()
+                            .map(ReservationHistoryView::from)
+                            .collect(),
+                    ),
+                }),
+                false => Err(AppError::Forbidden(
+                    "Not Authorize Reservations".to_string(),
+                )),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use chrono::Duration;
+    use labranet_common::{events::memory_bus::MemoryBus, pagination::Pagination, repository::RepoResult};
+    use mongodb::results::{DeleteResult, InsertOneResult, UpdateResult};
+
+    use super::*;
+    use crate::models::reservations::ReservationItemModel;
+
+    const ROOM_A: &str = "66f0c1a2b3c4d5e6f7a8b9c0";
+    const ROOM_B: &str = "66f0c1a2b3c4d5e6f7a8b9c1";
+
+    /// Reads only; the writes return driver results that cannot be built here.
+    struct FakeReservationRepo {
+        reservations: Vec<Reservation>,
+    }
+    #[async_trait]
+    impl ReservationRepoTrait for FakeReservationRepo {
+        async fn add(&self, _reservation: Reservation) -> RepoResult<InsertOneResult> {
+            unimplemented!("writes are not faked")
+        }
+        async fn find_all(
+            &self,
+            _owner: Option<ObjectId>,
+            _filter: ReservationFilterModel,
+            _pagination: &Pagination,
+        ) -> RepoResult<Page<Reservation>> {
+            unimplemented!("not used by these tests")
+        }
+        async fn find_active_by_rooms(&self, rooms: Vec<String>) -> RepoResult<Vec<Reservation>> {
+            Ok(self
+                .reservations
+                .iter()
+                .filter(|reservation| reservation.reservation_status.is_active())
+                .filter(|reservation| {
+                    reservation
+                        .items
+                        .iter()
+                        .any(|item| rooms.contains(&item.room))
+                })
+                .cloned()
+                .collect())
+        }
+        async fn update_status(
+            &self,
+            _id: ObjectId,
+            _from: ReservationStatus,
+            _to: ReservationStatus,
+            _cancel_reason: Option<String>,
+        ) -> RepoResult<UpdateResult> {
+            unimplemented!("writes are not faked")
+        }
+        async fn find_one(&self, _id: ObjectId) -> RepoResult<Option<Reservation>> {
+            Ok(self
+                .reservations
+                .iter()
+                .find(|reservation| reservation._id == _id)
+                .cloned())
+        }
+        async fn update(
+            &self,
+            _reservation: Reservation,
+            _id: ObjectId,
+            _status: ReservationStatus,
+        ) -> RepoResult<UpdateResult> {
+            unimplemented!("writes are not faked")
+        }
+        async fn delete(&self, _id: ObjectId) -> RepoResult<DeleteResult> {
+            unimplemented!("writes are not faked")
+        }
+    }
+
+    struct FakeRoomRepo {
+        rooms: Vec<Room>,
+    }
+    #[async_trait]
+    impl RoomRepoTrait for FakeRoomRepo {
+        async fn save(&self, _room: Room) -> RepoResult<UpdateResult> {
+            unimplemented!("writes are not faked")
+        }
+        async fn find_one(&self, _id: ObjectId) -> RepoResult<Option<Room>> {
+            Ok(self.rooms.iter().find(|room| room._id == _id).cloned())
+        }
+        async fn mark_deleted(&self, _id: ObjectId, _version: i64) -> RepoResult<UpdateResult> {
+            unimplemented!("writes are not faked")
+        }
+    }
+
+    /// Room locks without leases; `renew` fails once a lock changed hands.
+    #[derive(Clone, Default)]
+    struct FakeRoomLockRepo {
+        locks: Arc<Mutex<HashMap<String, ObjectId>>>,
+    }
+    #[async_trait]
+    impl RoomLockRepoTrait for FakeRoomLockRepo {
+        async fn acquire(&self, rooms: Vec<String>, owner: ObjectId) -> RepoResult<bool> {
+            let mut locks = self.locks.lock().unwrap();
+            if rooms.iter().any(|room| locks.get(room).is_some_and(|held| *held != owner)) {
+                return Ok(false);
+            }
+            for room in rooms {
+                locks.insert(room, owner);
             }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
+            Ok(true)
+        }
+        async fn renew(&self, rooms: Vec<String>, owner: ObjectId) -> RepoResult<bool> {
+            let locks = self.locks.lock().unwrap();
+            Ok(rooms.iter().all(|room| locks.get(room) == Some(&owner)))
+        }
+        async fn release(&self, rooms: Vec<String>, owner: ObjectId) -> RepoResult<()> {
+            let mut locks = self.locks.lock().unwrap();
+            for room in rooms {
+                if locks.get(&room) == Some(&owner) {
+                    locks.remove(&room);
+                }
             }
+            Ok(())
+        }
+    }
+
+    struct FakeHistoryRepo;
+    #[async_trait]
+    impl ReservationHistoryRepoTrait for FakeHistoryRepo {
+        async fn add(&self, _history: ReservationHistory) -> RepoResult<()> {
+            Ok(())
+        }
+        async fn find_all_by_reservation(
+            &self,
+            _reservation_id: ObjectId,
+        ) -> RepoResult<Vec<ReservationHistory>> {
+            Ok(Vec::new())
         }
     }
+
+    fn use_case(
+        reservations: Vec<Reservation>,
+        rooms: Vec<Room>,
+        locks: FakeRoomLockRepo,
+    ) -> ReservationUseCase {
+        ReservationUseCase::new(
+            Box::new(FakeReservationRepo { reservations }),
+            Box::new(locks),
+            Box::new(FakeRoomRepo { rooms }),
+            Box::new(FakeHistoryRepo),
+            Publisher::new(Arc::new(MemoryBus::new())),
+        )
+    }
+
+    fn date(value: &str) -> DateTime {
+        DateTime::parse_rfc3339_str(value).unwrap()
+    }
+
+    fn room(id: &str, price: f64) -> Room {
+        Room {
+            _id: ObjectId::parse_str(id).unwrap(),
+            floor_id: ObjectId::new(),
+            room_number: "101".to_string(),
+            name: "Meeting room".to_string(),
+            price,
+            is_deleted: false,
+            version: 1,
+        }
+    }
+
+    fn reservation(rooms: &[&str], start: &str, end: &str, status: ReservationStatus) -> Reservation {
+        Reservation {
+            _id: ObjectId::new(),
+            reservation_name: "Stand-up".to_string(),
+            description: "Daily stand-up".to_string(),
+            reservation_date: date(start),
+            reservation_status: status,
+            cancel_reason: None,
+            items: rooms
+                .iter()
+                .map(|room| ReservationItem {
+                    room: room.to_string(),
+                    price: 240.0,
+                    unit: PriceUnit::Night,
+                    quantity: 1,
+                    amount: 240.0,
+                })
+                .collect(),
+            reservation_start_date: date(start),
+            reservation_end_date: date(end),
+            total_price: 240.0,
+            created_by: ObjectId::new(),
+        }
+    }
+
+    fn model(rooms: &[&str], start: &str, end: &str) -> ReservationModel {
+        ReservationModel {
+            reservation_name: "Planning".to_string(),
+            description: "Sprint planning".to_string(),
+            reservation_date: date(start),
+            items: rooms
+                .iter()
+                .map(|room| ReservationItemModel {
+                    room: room.to_string(),
+                    price: None,
+                })
+                .collect(),
+            reservation_start_date: date(start),
+            reservation_end_date: date(end),
+        }
+    }
+
+    fn claims(subject: ObjectId, role: &str) -> Claims {
+        Claims::new(
+            subject.to_hex(),
+            "someone@labranet.test".to_string(),
+            role.to_string(),
+            ObjectId::new().to_hex(),
+            Duration::minutes(15),
+        )
+    }
+
+    fn booked_from_ten_to_noon(status: ReservationStatus) -> Reservation {
+        reservation(&[ROOM_A], "2024-10-01T10:00:00Z", "2024-10-01T12:00:00Z", status)
+    }
+
+    #[rocket::async_test]
+    async fn refuses_an_overlapping_reservation_and_releases_the_rooms() {
+        let locks = FakeRoomLockRepo::default();
+        let use_case = use_case(
+            vec![booked_from_ten_to_noon(ReservationStatus::Confirm)],
+            vec![],
+            locks.clone(),
+        );
+        let model = model(&[ROOM_A], "2024-10-01T11:00:00Z", "2024-10-01T13:00:00Z");
+
+        let result = use_case.lock_rooms(&model, None).await;
+
+        assert!(matches!(result, Err(AppError::Conflict { .. })));
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn accepts_a_reservation_starting_when_another_ends() {
+        let use_case = use_case(
+            vec![booked_from_ten_to_noon(ReservationStatus::Save)],
+            vec![],
+            FakeRoomLockRepo::default(),
+        );
+        let model = model(&[ROOM_A], "2024-10-01T12:00:00Z", "2024-10-01T14:00:00Z");
+
+        let (rooms, _) = use_case.lock_rooms(&model, None).await.unwrap();
+
+        assert_eq!(rooms, vec![ROOM_A.to_string()]);
+    }
+
+    #[rocket::async_test]
+    async fn ignores_reservations_that_no_longer_hold_the_room() {
+        let use_case = use_case(
+            vec![
+                booked_from_ten_to_noon(ReservationStatus::Cancel),
+                booked_from_ten_to_noon(ReservationStatus::NoShow),
+                booked_from_ten_to_noon(ReservationStatus::Complete),
+            ],
+            vec![],
+            FakeRoomLockRepo::default(),
+        );
+        let model = model(&[ROOM_A], "2024-10-01T11:00:00Z", "2024-10-01T13:00:00Z");
+
+        assert!(use_case.lock_rooms(&model, None).await.is_ok());
+    }
+
+    #[rocket::async_test]
+    async fn ignores_the_reservation_being_edited() {
+        let booked = booked_from_ten_to_noon(ReservationStatus::Save);
+        let id = booked._id;
+        let use_case = use_case(vec![booked], vec![], FakeRoomLockRepo::default());
+        let model = model(&[ROOM_A], "2024-10-01T11:00:00Z", "2024-10-01T13:00:00Z");
+
+        assert!(use_case.lock_rooms(&model, Some(id)).await.is_ok());
+    }
+
+    #[rocket::async_test]
+    async fn matches_room_ids_in_any_letter_case() {
+        let use_case = use_case(
+            vec![booked_from_ten_to_noon(ReservationStatus::Save)],
+            vec![],
+            FakeRoomLockRepo::default(),
+        );
+        let upper = ROOM_A.to_uppercase();
+        let overlapping = model(&[&upper], "2024-10-01T11:00:00Z", "2024-10-01T13:00:00Z");
+        let adjacent = model(&[&upper, ROOM_B], "2024-10-01T12:00:00Z", "2024-10-01T13:00:00Z");
+
+        let conflict = use_case.lock_rooms(&overlapping, None).await;
+        let (rooms, _) = use_case.lock_rooms(&adjacent, None).await.unwrap();
+
+        assert!(matches!(conflict, Err(AppError::Conflict { .. })));
+        assert_eq!(rooms, vec![ROOM_A.to_string(), ROOM_B.to_string()]);
+    }
+
+    #[rocket::async_test]
+    async fn locks_a_room_once_whatever_the_case_of_its_id() {
+        let locks = FakeRoomLockRepo::default();
+        let use_case = use_case(vec![], vec![], locks.clone());
+        let lower = model(&[ROOM_A], "2024-10-01T11:00:00Z", "2024-10-01T13:00:00Z");
+        let upper = model(&[&ROOM_A.to_uppercase()], "2024-10-01T11:00:00Z", "2024-10-01T13:00:00Z");
+
+        let first = use_case.lock_rooms(&lower, None).await;
+        let second = use_case.lock_rooms(&upper, None).await;
+
+        assert!(first.is_ok());
+        assert!(matches!(second, Err(AppError::Conflict { .. })));
+        assert_eq!(locks.locks.lock().unwrap().len(), 1);
+    }
+
+    #[rocket::async_test]
+    async fn refuses_to_write_once_the_room_lock_was_lost() {
+        let locks = FakeRoomLockRepo::default();
+        let use_case = use_case(vec![], vec![], locks.clone());
+        let model = model(&[ROOM_A], "2024-10-01T11:00:00Z", "2024-10-01T13:00:00Z");
+        let (rooms, owner) = use_case.lock_rooms(&model, None).await.unwrap();
+        // The lease ran out and another request took the room over.
+        locks.locks.lock().unwrap().insert(ROOM_A.to_string(), ObjectId::new());
+
+        let result = use_case.keep_rooms(&rooms, owner).await;
+
+        assert!(matches!(result, Err(AppError::Conflict { .. })));
+    }
+
+    #[rocket::async_test]
+    async fn rejects_a_room_listed_twice() {
+        let use_case = use_case(vec![], vec![room(ROOM_A, 240.0)], FakeRoomLockRepo::default());
+        let model = model(
+            &[ROOM_A, &ROOM_A.to_uppercase()],
+            "2024-10-01T11:00:00Z",
+            "2024-10-01T13:00:00Z",
+        );
+
+        let errors = use_case.validate_reservation(&model).await.unwrap();
+
+        assert_eq!(errors.field_errors().get("items").map(|errors| errors.len()), Some(1));
+    }
+
+    #[rocket::async_test]
+    async fn charges_stays_of_a_day_or_more_per_started_night() {
+        let use_case = use_case(vec![], vec![], FakeRoomLockRepo::default());
+        let room = room(ROOM_A, 240.0);
+
+        let two_nights = use_case.price_item(
+            &room,
+            &model(&[ROOM_A], "2024-10-01T12:00:00Z", "2024-10-03T12:00:00Z"),
+        );
+        let started_night = use_case.price_item(
+            &room,
+            &model(&[ROOM_A], "2024-10-01T12:00:00Z", "2024-10-02T13:00:00Z"),
+        );
+
+        assert_eq!(
+            (two_nights.unit, two_nights.quantity, two_nights.price, two_nights.amount),
+            (PriceUnit::Night, 2, 240.0, 480.0)
+        );
+        assert_eq!(
+            (started_night.unit, started_night.quantity, started_night.amount),
+            (PriceUnit::Night, 2, 480.0)
+        );
+    }
+
+    #[rocket::async_test]
+    async fn charges_shorter_stays_per_started_hour() {
+        let use_case = use_case(vec![], vec![], FakeRoomLockRepo::default());
+
+        let item = use_case.price_item(
+            &room(ROOM_A, 240.0),
+            &model(&[ROOM_A], "2024-10-01T10:00:00Z", "2024-10-01T11:30:00Z"),
+        );
+        let rounded = use_case.price_item(
+            &room(ROOM_A, 100.0),
+            &model(&[ROOM_A], "2024-10-01T10:00:00Z", "2024-10-01T13:00:00Z"),
+        );
+
+        assert_eq!(
+            (item.unit, item.quantity, item.price, item.amount),
+            (PriceUnit::Hour, 2, 10.0, 20.0)
+        );
+        assert_eq!((rounded.price, rounded.amount), (4.17, 12.51));
+        assert_eq!(item.room, ROOM_A);
+    }
+
+    #[rocket::async_test]
+    async fn rejects_a_price_the_service_would_not_charge() {
+        let use_case = use_case(vec![], vec![room(ROOM_A, 240.0)], FakeRoomLockRepo::default());
+        let mut wrong = model(&[ROOM_A], "2024-10-01T12:00:00Z", "2024-10-02T12:00:00Z");
+        wrong.items[0].price = Some(200.0);
+        let mut right = wrong.clone();
+        right.items[0].price = Some(240.0);
+
+        let errors = use_case.validate_rooms(&wrong).await.unwrap();
+
+        assert_eq!(errors, vec![format!("Price of room {} must be 240 per Night", ROOM_A)]);
+        assert!(use_case.validate_rooms(&right).await.unwrap().is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn refuses_to_price_a_missing_or_deleted_room() {
+        let deleted = Room { is_deleted: true, ..room(ROOM_B, 240.0) };
+        let use_case = use_case(vec![], vec![deleted], FakeRoomLockRepo::default());
+
+        let missing = use_case
+            .price_items(&model(&[ROOM_A], "2024-10-01T10:00:00Z", "2024-10-01T12:00:00Z"))
+            .await;
+        let gone = use_case
+            .price_items(&model(&[ROOM_B], "2024-10-01T10:00:00Z", "2024-10-01T12:00:00Z"))
+            .await;
+
+        assert!(matches!(missing, Err(AppError::Validation(_))));
+        assert!(matches!(gone, Err(AppError::Conflict { .. })));
+    }
+
+    #[rocket::async_test]
+    async fn totals_the_priced_rooms() {
+        let rooms = vec![room(ROOM_A, 240.0), room(ROOM_B, 100.0)];
+        let use_case = use_case(vec![], rooms, FakeRoomLockRepo::default());
+
+        let items = use_case
+            .price_items(&model(
+                &[ROOM_A, ROOM_B],
+                "2024-10-01T10:00:00Z",
+                "2024-10-01T13:00:00Z",
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(items.iter().map(|item| item.amount).collect::<Vec<_>>(), vec![30.0, 12.51]);
+        assert_eq!(total_price(&items), 42.51);
+    }
+
+    #[test]
+    fn lets_the_owner_or_a_manager_cancel() {
+        let use_case = use_case(vec![], vec![], FakeRoomLockRepo::default());
+        let booked = booked_from_ten_to_noon(ReservationStatus::Save);
+        let cancel = ReservationStatus::Cancel;
+
+        assert!(use_case.can_trigger(&claims(booked.created_by, "Worker"), &booked, &cancel));
+        assert!(use_case.can_trigger(&claims(ObjectId::new(), "Admin"), &booked, &cancel));
+        assert!(!use_case.can_trigger(&claims(ObjectId::new(), "Worker"), &booked, &cancel));
+    }
+
+    #[test]
+    fn keeps_other_transitions_to_status_managers() {
+        let use_case = use_case(vec![], vec![], FakeRoomLockRepo::default());
+        let booked = booked_from_ten_to_noon(ReservationStatus::Confirm);
+        let owner = claims(booked.created_by, "Worker");
+        let admin = claims(ObjectId::new(), "Admin");
+
+        for next in [
+            ReservationStatus::Confirm,
+            ReservationStatus::Complete,
+            ReservationStatus::NoShow,
+        ] {
+            assert!(!use_case.can_trigger(&owner, &booked, &next));
+            assert!(use_case.can_trigger(&admin, &booked, &next));
+        }
+    }
+
+    #[rocket::async_test]
+    async fn forbids_a_worker_to_confirm_their_own_reservation() {
+        let booked = booked_from_ten_to_noon(ReservationStatus::Save);
+        let (id, owner) = (booked._id.to_hex(), booked.created_by);
+        let use_case = use_case(vec![booked], vec![], FakeRoomLockRepo::default());
+        let key = Ok(JWT { claims: claims(owner, "Worker") });
+
+        let result = use_case
+            .transition_reservation(key, &id, ReservationStatus::Confirm, None)
+            .await;
+
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+    }
+
+    #[rocket::async_test]
+    async fn refuses_transitions_the_state_machine_does_not_allow() {
+        let saved = booked_from_ten_to_noon(ReservationStatus::Save);
+        let cancelled = booked_from_ten_to_noon(ReservationStatus::Cancel);
+        let (saved_id, cancelled_id) = (saved._id.to_hex(), cancelled._id.to_hex());
+        let use_case = use_case(vec![saved, cancelled], vec![], FakeRoomLockRepo::default());
+        let admin = || Ok(JWT { claims: claims(ObjectId::new(), "Admin") });
+
+        let skipped = use_case
+            .transition_reservation(admin(), &saved_id, ReservationStatus::Complete, None)
+            .await;
+        let reopened = use_case
+            .transition_reservation(admin(), &cancelled_id, ReservationStatus::Confirm, None)
+            .await;
+
+        assert!(matches!(skipped, Err(AppError::Conflict { .. })));
+        assert!(matches!(reopened, Err(AppError::Conflict { .. })));
+    }
+
+    #[rocket::async_test]
+    async fn requires_a_reason_to_cancel() {
+        let booked = booked_from_ten_to_noon(ReservationStatus::Save);
+        let (id, owner) = (booked._id.to_hex(), booked.created_by);
+        let use_case = use_case(vec![booked], vec![], FakeRoomLockRepo::default());
+        let key = Ok(JWT { claims: claims(owner, "Worker") });
+
+        let result = use_case
+            .transition_reservation(key, &id, ReservationStatus::Cancel, Some(" ".to_string()))
+            .await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[rocket::async_test]
+    async fn refuses_to_edit_a_closed_reservation() {
+        let use_case = use_case(vec![], vec![room(ROOM_A, 240.0)], FakeRoomLockRepo::default());
+        let completed = booked_from_ten_to_noon(ReservationStatus::Complete);
+        let actor = completed.created_by;
+        let model = model(&[ROOM_A], "2024-10-01T10:00:00Z", "2024-10-01T12:00:00Z");
+
+        let result = use_case.save_reservation(completed, model, actor).await;
+
+        assert!(matches!(result, Err(AppError::Conflict { .. })));
+    }
 }
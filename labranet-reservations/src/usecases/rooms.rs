@@ -0,0 +1,83 @@
+use labranet_common::events::{
+    rooms::{RoomCreatedEvent, RoomDeletedEvent, RoomUpdatedEvent},
+    EventError,
+};
+use labranet_common::repository::RepoError;
+use mongodb::bson::oid::ObjectId;
+use rocket::async_trait;
+
+use crate::{entities::rooms::Room, repositories::rooms::RoomRepoTrait};
+
+#[async_trait]
+pub trait RoomUseCaseTrait: Send + Sync {
+    async fn room_created(&self, event: RoomCreatedEvent) -> Result<(), EventError>;
+    async fn room_updated(&self, event: RoomUpdatedEvent) -> Result<(), EventError>;
+    async fn room_deleted(&self, event: RoomDeletedEvent) -> Result<(), EventError>;
+}
+
+pub struct RoomUseCase {
+    repo: Box<dyn RoomRepoTrait>,
+}
+impl RoomUseCase {
+    pub fn new(repo: Box<dyn RoomRepoTrait>) -> Self {
+        RoomUseCase { repo }
+    }
+    fn parse_id(&self, id: &str) -> Result<ObjectId, EventError> {
+        ObjectId::parse_str(id).map_err(|_| EventError::Handler(format!("Invalid id {}", id)))
+    }
+    async fn save_room(
+        &self,
+        id: String,
+        floor_id: String,
+        room_number: String,
+        name: String,
+        price: f64,
+        version: i64,
+    ) -> Result<(), EventError> {
+        let room = Room {
+            _id: self.parse_id(&id)?,
+            floor_id: self.parse_id(&floor_id)?,
+            room_number,
+            name,
+            price,
+            is_deleted: false,
+            version,
+        };
+        match self.repo.save(room).await {
+            // Deleted or changed by a newer event already; this one is stale.
+            Err(RepoError::Duplicate(_)) => Ok(()),
+            result => result.map(|_| ()).map_err(EventError::from),
+        }
+    }
+}
+
+#[async_trait]
+impl RoomUseCaseTrait for RoomUseCase {
+    async fn room_created(&self, event: RoomCreatedEvent) -> Result<(), EventError> {
+        self.save_room(
+            event.id,
+            event.floor_id,
+            event.room_number,
+            event.name,
+            event.price,
+            event.version,
+        )
+        .await
+    }
+    async fn room_updated(&self, event: RoomUpdatedEvent) -> Result<(), EventError> {
+        self.save_room(
+            event.id,
+            event.floor_id,
+            event.room_number,
+            event.name,
+            event.price,
+            event.version,
+        )
+        .await
+    }
+    async fn room_deleted(&self, event: RoomDeletedEvent) -> Result<(), EventError> {
+        let id = self.parse_id(&event.id)?;
+        self.repo.mark_deleted(id, event.version).await?;
+        Ok(())
+    }
+}
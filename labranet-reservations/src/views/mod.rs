@@ -0,0 +1,2 @@
+pub mod reservation_histories;
+pub mod reservations;
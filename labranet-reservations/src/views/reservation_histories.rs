@@ -0,0 +1,71 @@
+use mongodb::bson::serde_helpers::bson_datetime_as_rfc3339_string;
+use mongodb::bson::{Bson, DateTime};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::entities::reservation_histories::{
+    ReservationAction, ReservationFieldChange, ReservationHistory,
+};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReservationFieldChangeView {
+    pub field: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+impl From<ReservationFieldChange> for ReservationFieldChangeView {
+    fn from(change: ReservationFieldChange) -> Self {
+        ReservationFieldChangeView {
+            field: change.field,
+            before: change.before.map(plain_json),
+            after: change.after.map(plain_json),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReservationHistoryView {
+    pub id: String,
+    pub reservation_id: String,
+    pub actor_id: String,
+    pub action: ReservationAction,
+    #[serde(with = "bson_datetime_as_rfc3339_string")]
+    pub created_at: DateTime,
+    pub changes: Vec<ReservationFieldChangeView>,
+}
+impl From<ReservationHistory> for ReservationHistoryView {
+    fn from(history: ReservationHistory) -> Self {
+        ReservationHistoryView {
+            id: history._id.to_hex(),
+            reservation_id: history.reservation_id.to_hex(),
+            actor_id: history.actor_id.to_hex(),
+            action: history.action,
+            created_at: history.created_at,
+            changes: history
+                .changes
+                .into_iter()
+                .map(ReservationFieldChangeView::from)
+                .collect(),
+        }
+    }
+}
+
+/// The recorded values as plain JSON: ids as hex and dates as RFC 3339 rather
+/// than the `{"$oid": ...}` wrappers of extended JSON.
+fn plain_json(value: Bson) -> Value {
+    match value {
+        Bson::ObjectId(id) => Value::String(id.to_hex()),
+        Bson::DateTime(date) => match date.try_to_rfc3339_string() {
+            Ok(date) => Value::String(date),
+            Err(_) => Value::Null,
+        },
+        Bson::Array(values) => Value::Array(values.into_iter().map(plain_json).collect()),
+        Bson::Document(document) => Value::Object(
+            document
+                .into_iter()
+                .map(|(key, value)| (key, plain_json(value)))
+                .collect(),
+        ),
+        value => value.into_relaxed_extjson(),
+    }
+}
@@ -0,0 +1,65 @@
+use mongodb::bson::serde_helpers::bson_datetime_as_rfc3339_string;
+use mongodb::bson::DateTime;
+use serde::Serialize;
+
+use crate::entities::reservations::{PriceUnit, Reservation, ReservationItem};
+use labranet_common::reservation_status::ReservationStatus;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReservationItemView {
+    pub room: String,
+    pub price: f64,
+    pub unit: PriceUnit,
+    pub quantity: i64,
+    pub amount: f64,
+}
+impl From<ReservationItem> for ReservationItemView {
+    fn from(item: ReservationItem) -> Self {
+        ReservationItemView {
+            room: item.room,
+            price: item.price,
+            unit: item.unit,
+            quantity: item.quantity,
+            amount: item.amount,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReservationView {
+    pub id: String,
+    pub reservation_name: String,
+    pub description: String,
+    #[serde(with = "bson_datetime_as_rfc3339_string")]
+    pub reservation_date: DateTime,
+    pub reservation_status: ReservationStatus,
+    pub cancel_reason: Option<String>,
+    pub items: Vec<ReservationItemView>,
+    #[serde(with = "bson_datetime_as_rfc3339_string")]
+    pub reservation_start_date: DateTime,
+    #[serde(with = "bson_datetime_as_rfc3339_string")]
+    pub reservation_end_date: DateTime,
+    pub total_price: f64,
+    pub created_by: String,
+}
+impl From<Reservation> for ReservationView {
+    fn from(reservation: Reservation) -> Self {
+        ReservationView {
+            id: reservation._id.to_hex(),
+            reservation_name: reservation.reservation_name,
+            description: reservation.description,
+            reservation_date: reservation.reservation_date,
+            reservation_status: reservation.reservation_status,
+            cancel_reason: reservation.cancel_reason,
+            items: reservation
+                .items
+                .into_iter()
+                .map(ReservationItemView::from)
+                .collect(),
+            reservation_start_date: reservation.reservation_start_date,
+            reservation_end_date: reservation.reservation_end_date,
+            total_price: reservation.total_price,
+            created_by: reservation.created_by.to_hex(),
+        }
+    }
+}
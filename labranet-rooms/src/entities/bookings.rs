@@ -0,0 +1,21 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use labranet_common::reservation_status::ReservationStatus;
+use serde::{Deserialize, Serialize};
+
+/// A reservation as seen by the rooms service, kept up to date from reservation
+/// events. The `_id` is the reservation id. A status change or delete that arrives
+/// before the create leaves a document with only the status, version and
+/// `deleted`; the queries never match those since they are not active.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Booking {
+    pub _id: ObjectId,
+    pub rooms: Vec<String>,
+    pub reservation_start_date: DateTime,
+    pub reservation_end_date: DateTime,
+    pub status: ReservationStatus,
+    /// `version` of the last reservation event applied to this booking.
+    #[serde(default)]
+    pub version: i64,
+    #[serde(default)]
+    pub deleted: bool,
+}
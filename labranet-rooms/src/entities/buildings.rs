@@ -1,9 +1,30 @@
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
+use super::rooms::Room;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Building {
     pub _id : ObjectId,
     pub name : String,
     pub create_by :ObjectId
-}
\ No newline at end of file
+}
+
+/// A building with its floors and their rooms embedded, as read by
+/// `BuildingRepoTrait::find_tree`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BuildingTree {
+    pub _id : ObjectId,
+    pub name : String,
+    pub create_by :ObjectId,
+    pub floors : Vec<FloorTree>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FloorTree {
+    pub _id : ObjectId,
+    pub building_id:ObjectId,
+    pub name:String,
+    pub create_by:ObjectId,
+    pub rooms:Vec<Room>
+}
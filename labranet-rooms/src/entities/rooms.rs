@@ -9,6 +9,5 @@ pub struct Room {
     pub room_number:String,
     pub name:String,
     pub price:f64,
-    pub is_reservation:bool,
     pub create_by:ObjectId
 }
\ No newline at end of file
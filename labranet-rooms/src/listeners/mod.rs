@@ -0,0 +1,2 @@
+pub mod reservations;
+pub mod rooms;
\ No newline at end of file
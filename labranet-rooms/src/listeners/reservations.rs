@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use labranet_common::events::{
+    reservations::{
+        ReservationCancelledEvent, ReservationCompletedEvent, ReservationCreatedEvent,
+        ReservationDeletedEvent, ReservationUpdatedEvent,
+    },
+    EventError, Listener,
+};
+
+use crate::usecases::bookings::BookingUseCaseTrait;
+
+pub async fn listen_reservation_events(
+    listener: &Listener,
+    booking_use_case: Arc<dyn BookingUseCaseTrait>,
+) -> Result<(), EventError> {
+    let use_case = booking_use_case.clone();
+    listener
+        .listen(move |event: ReservationCreatedEvent| {
+            let use_case = use_case.clone();
+            async move { use_case.reservation_created(event).await }
+        })
+        .await?;
+    let use_case = booking_use_case.clone();
+    listener
+        .listen(move |event: ReservationUpdatedEvent| {
+            let use_case = use_case.clone();
+            async move { use_case.reservation_updated(event).await }
+        })
+        .await?;
+    let use_case = booking_use_case.clone();
+    listener
+        .listen(move |event: ReservationCancelledEvent| {
+            let use_case = use_case.clone();
+            async move { use_case.reservation_cancelled(event).await }
+        })
+        .await?;
+    let use_case = booking_use_case.clone();
+    listener
+        .listen(move |event: ReservationCompletedEvent| {
+            let use_case = use_case.clone();
+            async move { use_case.reservation_completed(event).await }
+        })
+        .await?;
+    let use_case = booking_use_case;
+    listener
+        .listen(move |event: ReservationDeletedEvent| {
+            let use_case = use_case.clone();
+            async move { use_case.reservation_deleted(event).await }
+        })
+        .await?;
+    Ok(())
+}
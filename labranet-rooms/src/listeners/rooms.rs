@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use labranet_common::events::{rooms::RoomsSyncRequestedEvent, EventError, Listener};
+
+use crate::usecases::room_sync::RoomSyncUseCaseTrait;
+
+pub async fn listen_room_sync_requests(
+    listener: &Listener,
+    room_sync_use_case: Arc<dyn RoomSyncUseCaseTrait>,
+) -> Result<(), EventError> {
+    listener
+        .listen(move |event: RoomsSyncRequestedEvent| {
+            let use_case = room_sync_use_case.clone();
+            async move { use_case.sync_requested(event).await }
+        })
+        .await?;
+    Ok(())
+}
@@ -1,7 +1,14 @@
 #[macro_use]
 extern crate rocket;
+use labranet_common::error::AppError;
+use labranet_common::events;
+use labranet_common::events::Listener;
+use labranet_common::events::Publisher;
+use labranet_common::jwks::PublicKeys;
 use labranet_common::response::ResponseError;
 use labranet_common::response::ResponseErrorBody;
+use labranet_common::revocations::listen_revocations;
+use labranet_common::revocations::Denylist;
 use labranet_rooms::db::db::connect;
 use labranet_rooms::db::db::MongoDB;
 use labranet_rooms::handlers::rooms::delete_building;
@@ -10,6 +17,10 @@ use labranet_rooms::handlers::rooms::delete_room;
 use labranet_rooms::handlers::rooms::fetch_all_building;
 use labranet_rooms::handlers::rooms::fetch_all_floor;
 use labranet_rooms::handlers::rooms::fetch_all_room;
+use labranet_rooms::handlers::rooms::fetch_available_rooms;
+use labranet_rooms::handlers::rooms::fetch_building_floors;
+use labranet_rooms::handlers::rooms::fetch_building_tree;
+use labranet_rooms::handlers::rooms::fetch_floor_rooms;
 use labranet_rooms::handlers::rooms::fetch_one_building;
 use labranet_rooms::handlers::rooms::fetch_one_floor;
 use labranet_rooms::handlers::rooms::fetch_one_room;
@@ -19,12 +30,21 @@ use labranet_rooms::handlers::rooms::new_room;
 use labranet_rooms::handlers::rooms::update_building;
 use labranet_rooms::handlers::rooms::update_floor;
 use labranet_rooms::handlers::rooms::update_room;
+use labranet_rooms::listeners::reservations::listen_reservation_events;
+use labranet_rooms::listeners::rooms::listen_room_sync_requests;
+use labranet_rooms::repositories::bookings::BookingRepo;
+use labranet_rooms::repositories::bookings::BookingRepoTrait;
 use labranet_rooms::repositories::buildings::BuildingRepo;
 use labranet_rooms::repositories::buildings::BuildingRepoTrait;
 use labranet_rooms::repositories::floors::FloorRepo;
 use labranet_rooms::repositories::floors::FloorRepoTrait;
 use labranet_rooms::repositories::rooms::RoomRepo;
 use labranet_rooms::repositories::rooms::RoomRepoTrait;
+use labranet_rooms::usecases::bookings::BookingUseCase;
+use labranet_rooms::usecases::bookings::BookingUseCaseTrait;
+use labranet_rooms::usecases::room_sync::RoomSyncUseCase;
+use labranet_rooms::usecases::room_sync::RoomSyncUseCaseTrait;
+use labranet_rooms::usecases::rooms::DeletePolicy;
 use labranet_rooms::usecases::rooms::RoomUseCase;
 use labranet_rooms::usecases::rooms::RoomUseCaseTrait;
 use rocket::http::Method;
@@ -32,6 +52,7 @@ use rocket::serde::json::Json;
 use rocket::shield::Allow;
 use rocket::shield::Permission;
 use rocket::shield::Shield;
+use std::sync::Arc;
 use rocket_cors::{AllowedOrigins, CorsOptions};
 
 
@@ -54,15 +75,27 @@ async fn rocket() -> _ {
         )
         .allow_credentials(true);
     let database = connect().await.unwrap();
-   // let nats = nats::connect(env::var("NATS_URL").expect("nat url not config")).unwrap();
+    let event_bus = events::connect().await.unwrap();
+    let publisher = Publisher::new(event_bus.clone());
+    let sync_publisher = Publisher::new(event_bus.clone());
+    let listener = Listener::new(event_bus.clone(), "labranet-rooms".to_string());
+    let denylist = Arc::new(Denylist::new());
+    listen_revocations(event_bus, "labranet-rooms", denylist.clone()).await.unwrap();
     let mongo = MongoDB::new(database);
     let building_repo:Box<dyn BuildingRepoTrait> = Box::new(BuildingRepo::new(mongo.clone()));
     let floor_repo:Box<dyn FloorRepoTrait> = Box::new(FloorRepo::new(mongo.clone()));
     let room_repo:Box<dyn RoomRepoTrait> = Box::new(RoomRepo::new(mongo.clone()));
-    let room_use_case :Box<dyn RoomUseCaseTrait> = Box::new(RoomUseCase::new(building_repo, floor_repo, room_repo));
+    let booking_repo:Box<dyn BookingRepoTrait> = Box::new(BookingRepo::new(mongo.clone()));
+    let room_use_case :Box<dyn RoomUseCaseTrait> = Box::new(RoomUseCase::new(building_repo, floor_repo, room_repo, booking_repo, publisher, DeletePolicy::from_env().unwrap()));
+    let booking_use_case:Arc<dyn BookingUseCaseTrait> = Arc::new(BookingUseCase::new(Box::new(BookingRepo::new(mongo.clone()))));
+    listen_reservation_events(&listener, booking_use_case).await.unwrap();
+    let room_sync_use_case:Arc<dyn RoomSyncUseCaseTrait> = Arc::new(RoomSyncUseCase::new(Box::new(RoomRepo::new(mongo.clone())), sync_publisher));
+    listen_room_sync_requests(&listener, room_sync_use_case).await.unwrap();
     rocket::build()
         .attach(Shield::default().enable(permission))
         .manage(room_use_case)
+        .manage(denylist)
+        .manage(PublicKeys::from_env())
         .manage(cors.to_cors())
         .mount(
             "/api/v1",
@@ -70,15 +103,19 @@ async fn rocket() -> _ {
                 new_building,
                 fetch_all_building,
                 fetch_one_building,
+                fetch_building_floors,
+                fetch_building_tree,
                 update_building,
                 delete_building,
                 new_floor,
                 fetch_all_floor,
                 fetch_one_floor,
+                fetch_floor_rooms,
                 update_floor,
                 delete_floor,
                 new_room,
                 fetch_all_room,
+                fetch_available_rooms,
                 fetch_one_room,
                 update_room,
                 delete_room
@@ -86,33 +123,29 @@ async fn rocket() -> _ {
             ],
         )
         .register("/", 
-        catchers![unauthorized,not_found,internal_sever_error,bad_gateway]
+        catchers![unauthorized,forbidden,not_found,internal_sever_error,bad_gateway]
     )
        
 }
 
 #[catch(401)]
-pub fn unauthorized() -> Json<ResponseError<String>> {
-    let response = ResponseError{
-        error:ResponseErrorBody::<String>::Error("Unauthorize".to_string())
-    };
-    Json(response)
+pub fn unauthorized() -> AppError {
+    AppError::Unauthorized("Unauthorize".to_string())
+}
+
+#[catch(403)]
+pub fn forbidden() -> AppError {
+    AppError::Forbidden("Forbidden".to_string())
 }
 
 #[catch(404)]
-pub fn not_found() -> Json<ResponseError<String>> {
-    let response = ResponseError{
-        error:ResponseErrorBody::<String>::Error("Not Found".to_string())
-    };
-    Json(response)
+pub fn not_found() -> AppError {
+    AppError::NotFound("Not Found".to_string())
 }
 
 #[catch(500)]
-pub fn internal_sever_error() -> Json<ResponseError<String>> {
-    let response = ResponseError{
-        error:ResponseErrorBody::<String>::Error("UNKNOWN Json".to_string())
-    };
-    Json(response)
+pub fn internal_sever_error() -> AppError {
+    AppError::Internal("UNKNOWN Json".to_string())
 }
 
 #[catch(502)]
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct BuildingModel {
+    #[validate(length(min = 1, max = 100, message = "Name is required and at most 100 characters"))]
     pub name : String,
 }
\ No newline at end of file
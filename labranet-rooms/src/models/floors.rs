@@ -1,8 +1,19 @@
+use labranet_common::validation::object_id;
+use rocket::FromForm;
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct FloorModel {
+    #[validate(custom(function = "object_id", message = "building must be provided"))]
     pub building_id:String,
+    #[validate(length(min = 1, max = 100, message = "building name must be provided and at most 100 characters"))]
     pub name:String,
     
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, FromForm, Validate)]
+pub struct FloorFilterModel {
+    #[validate(custom(function = "object_id", message = "building must be a valid id"))]
+    pub building_id:Option<String>,
+}
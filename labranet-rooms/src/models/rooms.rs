@@ -1,11 +1,42 @@
 
 
+use labranet_common::validation::{object_id, rfc3339};
+use rocket::FromForm;
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct RoomModel {
+    #[validate(custom(function = "object_id", message = "floor must be provided"))]
     pub floor_id:String,
+    #[validate(length(min = 1, max = 20, message = "room number must be provided and at most 20 characters"))]
     pub room_number:String,
+    #[validate(length(max = 100, message = "room name must be at most 100 characters"))]
     pub name:String,
+    #[validate(range(exclusive_min = 0.0, message = "room price must be positive"))]
     pub price:f64
-}
\ No newline at end of file
+}
+
+/// Query of `GET /rooms/available`. Dates are RFC 3339 and the window is half-open,
+/// so a room freed at `start` is available. `start < end` and the price range are
+/// checked by the use case.
+#[derive(Debug, Serialize, Deserialize, Clone, FromForm, Validate)]
+pub struct RoomAvailabilityModel {
+    #[validate(custom(function = "rfc3339", message = "start must be an RFC 3339 date"))]
+    pub start: String,
+    #[validate(custom(function = "rfc3339", message = "end must be an RFC 3339 date"))]
+    pub end: String,
+    #[validate(custom(function = "object_id", message = "building must be a valid id"))]
+    pub building_id: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
+
+/// Query of `GET /rooms`. The price range is checked by the use case.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, FromForm, Validate)]
+pub struct RoomFilterModel {
+    #[validate(custom(function = "object_id", message = "floor must be a valid id"))]
+    pub floor_id: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
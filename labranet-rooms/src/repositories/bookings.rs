@@ -0,0 +1,111 @@
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use mongodb::results::UpdateResult;
+use rocket::async_trait;
+use rocket::futures::TryStreamExt;
+
+use labranet_common::repository::{retry, RepoResult};
+use labranet_common::reservation_status::ReservationStatus;
+
+use crate::{db::db::MongoDB, entities::bookings::Booking};
+
+#[async_trait]
+pub trait BookingRepoTrait: Send + Sync {
+    async fn save(&self, booking: Booking) -> RepoResult<UpdateResult>;
+    async fn update_status(
+        &self,
+        _id: ObjectId,
+        status: ReservationStatus,
+        version: i64,
+    ) -> RepoResult<UpdateResult>;
+    async fn mark_deleted(&self, _id: ObjectId, version: i64) -> RepoResult<UpdateResult>;
+    async fn find_active_by_rooms(
+        &self,
+        rooms: Vec<String>,
+        from: DateTime,
+    ) -> RepoResult<Vec<Booking>>;
+    async fn find_overlapping(&self, start: DateTime, end: DateTime) -> RepoResult<Vec<Booking>>;
+}
+
+pub struct BookingRepo {
+    mongo: MongoDB,
+}
+impl BookingRepo {
+    pub fn new(mongo: MongoDB) -> Self {
+        BookingRepo { mongo }
+    }
+    /// Matches the booking unless it is deleted or already holds `version` or newer.
+    /// An upsert with this filter on such a booking tries to insert the same `_id`
+    /// again and fails with `RepoError::Duplicate`, which callers treat as stale.
+    fn older_than(&self, _id: ObjectId, version: i64) -> Document {
+        doc! {"_id":_id,"deleted":{"$ne":true},"version":{"$not":{"$gte":version}}}
+    }
+}
+#[async_trait]
+impl BookingRepoTrait for BookingRepo {
+    async fn save(&self, booking: Booking) -> RepoResult<UpdateResult> {
+        let col = self.mongo.database.collection::<Booking>("bookings");
+        let update = doc! {"$set":doc!{
+            "rooms":booking.rooms,
+            "reservation_start_date":booking.reservation_start_date,
+            "reservation_end_date":booking.reservation_end_date,
+            "status":booking.status.to_string(),
+            "version":booking.version
+        }};
+        let filter = self.older_than(booking._id, booking.version);
+        retry(|| col.update_one(filter.clone(), update.clone()).upsert(true)).await
+    }
+    /// Upserted so a status change that arrives before the create is not lost.
+    async fn update_status(
+        &self,
+        _id: ObjectId,
+        status: ReservationStatus,
+        version: i64,
+    ) -> RepoResult<UpdateResult> {
+        let col = self.mongo.database.collection::<Booking>("bookings");
+        let update = doc! {"$set":{"status":status.to_string(),"version":version}};
+        let filter = self.older_than(_id, version);
+        retry(|| col.update_one(filter.clone(), update.clone()).upsert(true)).await
+    }
+    /// Keeps a tombstone so a late create or update does not bring the booking back.
+    async fn mark_deleted(&self, _id: ObjectId, version: i64) -> RepoResult<UpdateResult> {
+        let col = self.mongo.database.collection::<Booking>("bookings");
+        let update = doc! {"$set":{"deleted":true},"$max":{"version":version}};
+        retry(|| col.update_one(doc! {"_id":_id}, update.clone()).upsert(true)).await
+    }
+    async fn find_active_by_rooms(
+        &self,
+        rooms: Vec<String>,
+        from: DateTime,
+    ) -> RepoResult<Vec<Booking>> {
+        let col = self.mongo.database.collection::<Booking>("bookings");
+        let filter = doc! {
+            "rooms":{"$in":rooms},
+            "deleted":{"$ne":true},
+            "status":{"$in":ReservationStatus::active_names()},
+            "reservation_end_date":{"$gt":from}
+        };
+        retry(|| async {
+            col.find(filter.clone())
+                .await?
+                .try_collect::<Vec<Booking>>()
+                .await
+        })
+        .await
+    }
+    async fn find_overlapping(&self, start: DateTime, end: DateTime) -> RepoResult<Vec<Booking>> {
+        let col = self.mongo.database.collection::<Booking>("bookings");
+        let filter = doc! {
+            "deleted":{"$ne":true},
+            "status":{"$in":ReservationStatus::active_names()},
+            "reservation_start_date":{"$lt":end},
+            "reservation_end_date":{"$gt":start}
+        };
+        retry(|| async {
+            col.find(filter.clone())
+                .await?
+                .try_collect::<Vec<Booking>>()
+                .await
+        })
+        .await
+    }
+}
@@ -1,16 +1,19 @@
-use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::bson::{doc, from_document, oid::ObjectId};
 use mongodb::results::{DeleteResult, InsertOneResult, UpdateResult};
 use rocket::async_trait;
-use rocket::futures::StreamExt;
-use crate::{db::db::MongoDB, entities::buildings::Building};
+use labranet_common::pagination::{Page, Pagination};
+use labranet_common::repository::{retry, RepoError, RepoResult};
+use rocket::futures::TryStreamExt;
+use crate::{db::db::MongoDB, entities::buildings::{Building, BuildingTree}};
 
 #[async_trait]
 pub trait  BuildingRepoTrait : Send+Sync {
-    async fn add(&self,building:Building)->InsertOneResult ;
-    async fn find_all(&self)->Vec<Building>;
-    async fn find_one(&self,_id:ObjectId)->Option<Building>;
-    async fn update(&self,building:Building,_id:ObjectId)->UpdateResult;
-    async fn delete(&self,_id:ObjectId)->DeleteResult;
+    async fn add(&self,building:Building)->RepoResult<InsertOneResult> ;
+    async fn find_all(&self,pagination:&Pagination)->RepoResult<Page<Building>>;
+    async fn find_one(&self,_id:ObjectId)->RepoResult<Option<Building>>;
+    async fn find_tree(&self,_id:ObjectId)->RepoResult<Option<BuildingTree>>;
+    async fn update(&self,building:Building,_id:ObjectId)->RepoResult<UpdateResult>;
+    async fn delete(&self,_id:ObjectId)->RepoResult<DeleteResult>;
 }
 
 pub struct BuildingRepo {
@@ -23,43 +26,76 @@ impl  BuildingRepo {
 }
 #[async_trait]
 impl BuildingRepoTrait for BuildingRepo {
-    async fn add(&self,building:Building)->InsertOneResult{
+    async fn add(&self,building:Building)->RepoResult<InsertOneResult>{
         let col = self.mongo.database.collection::<Building>("buildings");
         let new_building = Building{
             _id:building._id,
             name:building.name,
             create_by:building.create_by
         };
-        col.insert_one(&new_building).await.unwrap()
+        Ok(col.insert_one(&new_building).await?)
    }
-   async fn find_all(&self)->Vec<Building> {
+   async fn find_all(&self,pagination:&Pagination)->RepoResult<Page<Building>> {
     let col = self.mongo.database.collection::<Building>("buildings");
-    let mut  cursor = col.find(doc! {}).await.unwrap();
-    let mut results:Vec<Building> = Vec::new();
-    while let Some(result) = cursor.next().await {
-        if result.is_ok(){
-         results.push(result.unwrap());
-        }
-         
-    }
-    results 
+    let total = retry(|| col.count_documents(doc! {})).await?;
+    let results = retry(|| async {
+        col.find(doc! {})
+            .sort(doc! {pagination.sort_field.as_str():pagination.sort_order})
+            .skip(pagination.skip())
+            .limit(pagination.limit as i64)
+            .await?
+            .try_collect::<Vec<Building>>()
+            .await
+    }).await?;
+    Ok(Page::new(results, total, pagination))
    }
-   async fn find_one(&self,_id:ObjectId)->Option<Building>{
+   async fn find_one(&self,_id:ObjectId)->RepoResult<Option<Building>>{
         let col = self.mongo.database.collection::<Building>("buildings");
-        let result = col.find_one(doc! {"_id":_id}).await.unwrap();
-        result
+        retry(|| col.find_one(doc! {"_id":_id})).await
+   }
+   /// Joins the building's floors and each floor's rooms in a single aggregation.
+   async fn find_tree(&self,_id:ObjectId)->RepoResult<Option<BuildingTree>>{
+        let col = self.mongo.database.collection::<Building>("buildings");
+        let pipeline = vec![
+            doc! {"$match":{"_id":_id}},
+            doc! {"$lookup":{
+                "from":"floors",
+                "localField":"_id",
+                "foreignField":"building_id",
+                "pipeline":[
+                    {"$lookup":{
+                        "from":"rooms",
+                        "localField":"_id",
+                        "foreignField":"floor_id",
+                        "pipeline":[{"$sort":{"room_number":1}}],
+                        "as":"rooms"
+                    }},
+                    {"$sort":{"name":1}}
+                ],
+                "as":"floors"
+            }},
+        ];
+        let document = retry(|| async {
+            col.aggregate(pipeline.clone()).await?.try_next().await
+        }).await?;
+        match document {
+            Some(document) => from_document::<BuildingTree>(document)
+                .map(Some)
+                .map_err(|err| RepoError::Database(err.to_string())),
+            None => Ok(None),
+        }
    }
-   async fn update(&self,building:Building,_id:ObjectId)->UpdateResult{
+   async fn update(&self,building:Building,_id:ObjectId)->RepoResult<UpdateResult>{
     let col = self.mongo.database.collection::<Building>("buildings");
-    let result=col.update_one(doc! {"_id":_id},doc! {
+    let update = doc! {
         "$set":doc!{
             "name":building.name
         }
-    }).await.unwrap();
-    result
+    };
+    retry(|| col.update_one(doc! {"_id":_id},update.clone())).await
    }
-   async fn delete(&self,_id:ObjectId)->DeleteResult{
+   async fn delete(&self,_id:ObjectId)->RepoResult<DeleteResult>{
     let col = self.mongo.database.collection::<Building>("buildings");
-    col.delete_one(doc! {"_id":_id}).await.unwrap()
+    retry(|| col.delete_one(doc! {"_id":_id})).await
    }
 }
\ No newline at end of file
@@ -1,18 +1,26 @@
+use labranet_common::pagination::{Page, Pagination};
+use labranet_common::repository::{retry, RepoResult};
 use mongodb::bson::{doc, oid::ObjectId};
 use mongodb::results::{DeleteResult, InsertOneResult, UpdateResult};
 use rocket::async_trait;
-use rocket::futures::StreamExt;
+use rocket::futures::TryStreamExt;
 
 use crate::db::db::MongoDB;
 use crate::entities::floors::Floor;
+use crate::models::floors::FloorFilterModel;
 
 #[async_trait]
 pub trait FloorRepoTrait: Send + Sync {
-    async fn add(&self, floor: Floor) -> InsertOneResult;
-    async fn find_all(&self) -> Vec<Floor>;
-    async fn find_one(&self, _id: ObjectId) -> Option<Floor>;
-    async fn update(&self, floor: Floor, _id: ObjectId) -> UpdateResult;
-    async fn delete(&self, _id: ObjectId) -> DeleteResult;
+    async fn add(&self, floor: Floor) -> RepoResult<InsertOneResult>;
+    async fn find_all(
+        &self,
+        filter: FloorFilterModel,
+        pagination: &Pagination,
+    ) -> RepoResult<Page<Floor>>;
+    async fn find_one(&self, _id: ObjectId) -> RepoResult<Option<Floor>>;
+    async fn find_by_building(&self, building_id: ObjectId) -> RepoResult<Vec<Floor>>;
+    async fn update(&self, floor: Floor, _id: ObjectId) -> RepoResult<UpdateResult>;
+    async fn delete(&self, _id: ObjectId) -> RepoResult<DeleteResult>;
 }
 
 pub struct FloorRepo {
@@ -25,7 +33,7 @@ impl FloorRepo {
 }
 #[async_trait]
 impl FloorRepoTrait for FloorRepo {
-    async fn add(&self, floor: Floor) -> InsertOneResult {
+    async fn add(&self, floor: Floor) -> RepoResult<InsertOneResult> {
         let col = self.mongo.database.collection::<Floor>("floors");
         let new_floor = Floor {
             _id: floor._id,
@@ -33,40 +41,57 @@ impl FloorRepoTrait for FloorRepo {
             name: floor.name,
             create_by: floor.create_by,
         };
-        col.insert_one(&new_floor).await.unwrap()
+        Ok(col.insert_one(&new_floor).await?)
     }
-    async fn find_all(&self) -> Vec<Floor> {
+    async fn find_all(
+        &self,
+        filter: FloorFilterModel,
+        pagination: &Pagination,
+    ) -> RepoResult<Page<Floor>> {
         let col = self.mongo.database.collection::<Floor>("floors");
-        let mut cursor = col.find(doc! {}).await.unwrap();
-        let mut results: Vec<Floor> = Vec::new();
-        while let Some(result) = cursor.next().await {
-            if result.is_ok() {
-                results.push(result.unwrap());
-            }
+        let mut query = doc! {};
+        if let Some(building_id) = filter.building_id {
+            query.insert("building_id", ObjectId::parse_str(building_id).unwrap());
         }
-        results
+        let total = retry(|| col.count_documents(query.clone())).await?;
+        let results = retry(|| async {
+            col.find(query.clone())
+                .sort(doc! {pagination.sort_field.as_str():pagination.sort_order})
+                .skip(pagination.skip())
+                .limit(pagination.limit as i64)
+                .await?
+                .try_collect::<Vec<Floor>>()
+                .await
+        })
+        .await?;
+        Ok(Page::new(results, total, pagination))
     }
-    async fn find_one(&self, _id: ObjectId) -> Option<Floor> {
+    async fn find_one(&self, _id: ObjectId) -> RepoResult<Option<Floor>> {
         let col = self.mongo.database.collection::<Floor>("floors");
-        let result = col.find_one(doc! {"_id":_id}).await.unwrap();
-        result
+        retry(|| col.find_one(doc! {"_id":_id})).await
     }
-    async fn update(&self, floor: Floor, _id: ObjectId) -> UpdateResult {
+    async fn find_by_building(&self, building_id: ObjectId) -> RepoResult<Vec<Floor>> {
         let col = self.mongo.database.collection::<Floor>("floors");
-        col.update_one(
-            doc! {"_id":_id},
-            doc! {
-                "$set":doc!{
-                    "building_id":floor.building_id,
-                    "name":floor.name
-                }
-            },
-        )
+        retry(|| async {
+            col.find(doc! {"building_id":building_id})
+                .await?
+                .try_collect::<Vec<Floor>>()
+                .await
+        })
         .await
-        .unwrap()
     }
-    async fn delete(&self, _id: ObjectId) -> DeleteResult {
+    async fn update(&self, floor: Floor, _id: ObjectId) -> RepoResult<UpdateResult> {
+        let col = self.mongo.database.collection::<Floor>("floors");
+        let update = doc! {
+            "$set":doc!{
+                "building_id":floor.building_id,
+                "name":floor.name
+            }
+        };
+        retry(|| col.update_one(doc! {"_id":_id}, update.clone())).await
+    }
+    async fn delete(&self, _id: ObjectId) -> RepoResult<DeleteResult> {
         let col = self.mongo.database.collection::<Floor>("floors");
-        col.delete_one(doc! {"_id":_id}).await.unwrap()
+        retry(|| col.delete_one(doc! {"_id":_id})).await
     }
 }
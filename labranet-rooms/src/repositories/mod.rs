@@ -1,3 +1,4 @@
+pub mod bookings;
 pub mod buildings;
 pub mod floors;
 pub mod rooms;
\ No newline at end of file
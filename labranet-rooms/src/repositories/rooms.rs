@@ -1,17 +1,23 @@
-use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::bson::{doc, oid::ObjectId, Document};
 use mongodb::results::{DeleteResult, InsertOneResult, UpdateResult};
 use rocket::async_trait;
-use rocket::futures::StreamExt;
+use labranet_common::pagination::{Page, Pagination};
+use labranet_common::repository::{retry, RepoResult};
+use rocket::futures::TryStreamExt;
 use crate::db::db::MongoDB;
 use crate::entities::rooms::Room;
+use crate::models::rooms::RoomFilterModel;
 
 #[async_trait]
 pub trait  RoomRepoTrait : Send+Sync {
-    async fn add(&self,room:Room)->InsertOneResult ;
-    async fn find_all(&self)->Vec<Room>;
-    async fn find_one(&self,_id:ObjectId)->Option<Room>;
-    async fn update(&self,room:Room,_id:ObjectId)->UpdateResult;
-    async fn delete(&self,_id:ObjectId)->DeleteResult;
+    async fn add(&self,room:Room)->RepoResult<InsertOneResult> ;
+    async fn find_all(&self,filter:RoomFilterModel,pagination:&Pagination)->RepoResult<Page<Room>>;
+    async fn find_one(&self,_id:ObjectId)->RepoResult<Option<Room>>;
+    async fn find_by_floors(&self,floor_ids:Vec<ObjectId>)->RepoResult<Vec<Room>>;
+    async fn find_every(&self)->RepoResult<Vec<Room>>;
+    async fn find_available(&self,floor_ids:Option<Vec<ObjectId>>,excluded:Vec<ObjectId>,min_price:Option<f64>,max_price:Option<f64>)->RepoResult<Vec<Room>>;
+    async fn update(&self,room:Room,_id:ObjectId)->RepoResult<UpdateResult>;
+    async fn delete(&self,_id:ObjectId)->RepoResult<DeleteResult>;
 }
 
 pub struct RoomRepo {
@@ -21,11 +27,24 @@ impl  RoomRepo {
     pub fn new(mongo:MongoDB)->Self{
         RoomRepo{mongo}
     }
+    fn price_range(&self,min_price:Option<f64>,max_price:Option<f64>)->Option<Document>{
+        let mut price = Document::new();
+        if let Some(min_price) = min_price {
+            price.insert("$gte", min_price);
+        }
+        if let Some(max_price) = max_price {
+            price.insert("$lte", max_price);
+        }
+        match price.is_empty() {
+            true => None,
+            false => Some(price),
+        }
+    }
 
 }
 #[async_trait]
 impl RoomRepoTrait for RoomRepo {
-    async fn add(&self,room:Room)->InsertOneResult {
+    async fn add(&self,room:Room)->RepoResult<InsertOneResult> {
         let col = self.mongo.database.collection::<Room>("rooms");
         let new_room = Room{
             _id:room._id,
@@ -33,39 +52,81 @@ impl RoomRepoTrait for RoomRepo {
             room_number:room.room_number,
             name:room.name,
             price:room.price,
-            is_reservation:room.is_reservation,
             create_by:room.create_by
         };
-        col.insert_one(&new_room).await.unwrap()
+        Ok(col.insert_one(&new_room).await?)
     }
-    async fn find_all(&self)->Vec<Room>{
+    async fn find_all(&self,filter:RoomFilterModel,pagination:&Pagination)->RepoResult<Page<Room>>{
         let col = self.mongo.database.collection::<Room>("rooms");
-        let mut cursor = col.find(doc! {}).await.unwrap();
-        let mut results:Vec<Room> = Vec::new();
-        while let Some(result) = cursor.next().await {
-            if result.is_ok() {
-                results.push(result.unwrap());
-            }
+        let mut query = doc! {};
+        if let Some(floor_id) = filter.floor_id {
+            query.insert("floor_id", ObjectId::parse_str(floor_id).unwrap());
         }
-        results
+        if let Some(price) = self.price_range(filter.min_price, filter.max_price) {
+            query.insert("price", price);
+        }
+        let total = retry(|| col.count_documents(query.clone())).await?;
+        let results = retry(|| async {
+            col.find(query.clone())
+                .sort(doc! {pagination.sort_field.as_str():pagination.sort_order})
+                .skip(pagination.skip())
+                .limit(pagination.limit as i64)
+                .await?
+                .try_collect::<Vec<Room>>()
+                .await
+        }).await?;
+        Ok(Page::new(results, total, pagination))
+    }
+    async fn find_one(&self,_id:ObjectId)->RepoResult<Option<Room>>{
+        let col = self.mongo.database.collection::<Room>("rooms");
+        retry(|| col.find_one(doc! {"_id":_id})).await
     }
-    async fn find_one(&self,_id:ObjectId)->Option<Room>{
+    async fn find_by_floors(&self,floor_ids:Vec<ObjectId>)->RepoResult<Vec<Room>>{
         let col = self.mongo.database.collection::<Room>("rooms");
-        let result = col.find_one(doc! {"_id":_id}).await.unwrap();
-        result
+        retry(|| async {
+            col.find(doc! {"floor_id":{"$in":floor_ids.clone()}})
+                .await?
+                .try_collect::<Vec<Room>>()
+                .await
+        }).await
+    }
+    async fn find_every(&self)->RepoResult<Vec<Room>>{
+        let col = self.mongo.database.collection::<Room>("rooms");
+        retry(|| async {
+            col.find(doc! {})
+                .await?
+                .try_collect::<Vec<Room>>()
+                .await
+        }).await
+    }
+    async fn find_available(&self,floor_ids:Option<Vec<ObjectId>>,excluded:Vec<ObjectId>,min_price:Option<f64>,max_price:Option<f64>)->RepoResult<Vec<Room>>{
+        let col = self.mongo.database.collection::<Room>("rooms");
+        let mut filter = doc! {"_id":{"$nin":excluded}};
+        if let Some(floor_ids) = floor_ids {
+            filter.insert("floor_id", doc! {"$in":floor_ids});
+        }
+        if let Some(price) = self.price_range(min_price, max_price) {
+            filter.insert("price", price);
+        }
+        retry(|| async {
+            col.find(filter.clone())
+                .await?
+                .try_collect::<Vec<Room>>()
+                .await
+        }).await
     }
-    async fn update(&self,room:Room,_id:ObjectId)->UpdateResult{
+    async fn update(&self,room:Room,_id:ObjectId)->RepoResult<UpdateResult>{
         let col = self.mongo.database.collection::<Room>("rooms");
-        col.update_one(doc! {"_id":_id},doc! {"$set":doc!{
+        let update = doc! {"$set":doc!{
             "floor_id":room.floor_id,
             "room_number":room.room_number,
             "name":room.name,
-            "price":room.price,
-            "is_reservation":room.is_reservation
-        }}).await.unwrap()
+            "price":room.price
+        }};
+        retry(|| col.update_one(doc! {"_id":_id},update.clone())).await
     }
-    async fn delete(&self,_id:ObjectId)->DeleteResult{
+    async fn delete(&self,_id:ObjectId)->RepoResult<DeleteResult>{
         let col = self.mongo.database.collection::<Room>("rooms");
-        col.delete_one(doc! {"_id":_id}).await.unwrap()
+        retry(|| col.delete_one(doc! {"_id":_id})).await
     }
 }
\ No newline at end of file
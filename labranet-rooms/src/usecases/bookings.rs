@@ -0,0 +1,125 @@
+use labranet_common::events::{
+    reservations::{
+        ReservationCancelledEvent, ReservationCompletedEvent, ReservationCreatedEvent,
+        ReservationDeletedEvent, ReservationUpdatedEvent,
+    },
+    EventError,
+};
+use labranet_common::repository::{RepoError, RepoResult};
+use labranet_common::reservation_status::ReservationStatus;
+use mongodb::results::UpdateResult;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use rocket::async_trait;
+
+use crate::{entities::bookings::Booking, repositories::bookings::BookingRepoTrait};
+
+#[async_trait]
+pub trait BookingUseCaseTrait: Send + Sync {
+    async fn reservation_created(&self, event: ReservationCreatedEvent) -> Result<(), EventError>;
+    async fn reservation_updated(&self, event: ReservationUpdatedEvent) -> Result<(), EventError>;
+    async fn reservation_cancelled(
+        &self,
+        event: ReservationCancelledEvent,
+    ) -> Result<(), EventError>;
+    async fn reservation_completed(
+        &self,
+        event: ReservationCompletedEvent,
+    ) -> Result<(), EventError>;
+    async fn reservation_deleted(&self, event: ReservationDeletedEvent) -> Result<(), EventError>;
+}
+
+pub struct BookingUseCase {
+    repo: Box<dyn BookingRepoTrait>,
+}
+impl BookingUseCase {
+    pub fn new(repo: Box<dyn BookingRepoTrait>) -> Self {
+        BookingUseCase { repo }
+    }
+    fn parse_id(&self, id: &str) -> Result<ObjectId, EventError> {
+        ObjectId::parse_str(id).map_err(|_| EventError::Handler(format!("Invalid id {}", id)))
+    }
+    fn parse_date(&self, date: &str) -> Result<DateTime, EventError> {
+        DateTime::parse_rfc3339_str(date)
+            .map_err(|_| EventError::Handler(format!("Invalid date {}", date)))
+    }
+    async fn save_booking(
+        &self,
+        id: String,
+        rooms: Vec<String>,
+        reservation_start_date: String,
+        reservation_end_date: String,
+        status: ReservationStatus,
+        version: i64,
+    ) -> Result<(), EventError> {
+        let booking = Booking {
+            _id: self.parse_id(&id)?,
+            rooms,
+            reservation_start_date: self.parse_date(&reservation_start_date)?,
+            reservation_end_date: self.parse_date(&reservation_end_date)?,
+            status,
+            version,
+            deleted: false,
+        };
+        self.applied(self.repo.save(booking).await)
+    }
+    fn applied(&self, result: RepoResult<UpdateResult>) -> Result<(), EventError> {
+        match result {
+            // Deleted or changed by a newer event already; this one is stale.
+            Err(RepoError::Duplicate(_)) => Ok(()),
+            result => result.map(|_| ()).map_err(EventError::from),
+        }
+    }
+}
+
+#[async_trait]
+impl BookingUseCaseTrait for BookingUseCase {
+    async fn reservation_created(&self, event: ReservationCreatedEvent) -> Result<(), EventError> {
+        self.save_booking(
+            event.id,
+            event.rooms,
+            event.reservation_start_date,
+            event.reservation_end_date,
+            event.status,
+            event.version,
+        )
+        .await
+    }
+    async fn reservation_updated(&self, event: ReservationUpdatedEvent) -> Result<(), EventError> {
+        self.save_booking(
+            event.id,
+            event.rooms,
+            event.reservation_start_date,
+            event.reservation_end_date,
+            event.status,
+            event.version,
+        )
+        .await
+    }
+    async fn reservation_cancelled(
+        &self,
+        event: ReservationCancelledEvent,
+    ) -> Result<(), EventError> {
+        let id = self.parse_id(&event.id)?;
+        let result = self
+            .repo
+            .update_status(id, ReservationStatus::Cancel, event.version)
+            .await;
+        self.applied(result)
+    }
+    async fn reservation_completed(
+        &self,
+        event: ReservationCompletedEvent,
+    ) -> Result<(), EventError> {
+        let id = self.parse_id(&event.id)?;
+        let result = self
+            .repo
+            .update_status(id, ReservationStatus::Complete, event.version)
+            .await;
+        self.applied(result)
+    }
+    async fn reservation_deleted(&self, event: ReservationDeletedEvent) -> Result<(), EventError> {
+        let id = self.parse_id(&event.id)?;
+        self.repo.mark_deleted(id, event.version).await?;
+        Ok(())
+    }
+}
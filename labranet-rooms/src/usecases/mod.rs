@@ -1 +1,3 @@
+pub mod bookings;
+pub mod room_sync;
 pub mod rooms;
\ No newline at end of file
@@ -0,0 +1,45 @@
+use labranet_common::events::{
+    rooms::{RoomUpdatedEvent, RoomsSyncRequestedEvent},
+    EventError, Publisher,
+};
+use mongodb::bson::DateTime;
+use rocket::async_trait;
+
+use crate::repositories::rooms::RoomRepoTrait;
+
+#[async_trait]
+pub trait RoomSyncUseCaseTrait: Send + Sync {
+    async fn sync_requested(&self, event: RoomsSyncRequestedEvent) -> Result<(), EventError>;
+}
+
+/// Fills the room replicas of other services with the rooms they missed.
+pub struct RoomSyncUseCase {
+    repo: Box<dyn RoomRepoTrait>,
+    publisher: Publisher,
+}
+impl RoomSyncUseCase {
+    pub fn new(repo: Box<dyn RoomRepoTrait>, publisher: Publisher) -> Self {
+        RoomSyncUseCase { repo, publisher }
+    }
+}
+
+#[async_trait]
+impl RoomSyncUseCaseTrait for RoomSyncUseCase {
+    async fn sync_requested(&self, _event: RoomsSyncRequestedEvent) -> Result<(), EventError> {
+        let rooms = self.repo.find_every().await?;
+        println!("Publishing {} rooms for sync", rooms.len());
+        for room in rooms {
+            self.publisher
+                .publish(&RoomUpdatedEvent {
+                    id: room._id.to_hex(),
+                    floor_id: room.floor_id.to_hex(),
+                    room_number: room.room_number,
+                    name: room.name,
+                    price: room.price,
+                    version: DateTime::now().timestamp_millis(),
+                })
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -1,1128 +1,805 @@
+use dotenvy::dotenv;
 use labranet_common::{
-    jwt::JWT,
-    response::{Response, ResponseBody, ResponseError, ResponseErrorBody},
+    error::AppError,
+    events::{
+        rooms::{RoomCreatedEvent, RoomDeletedEvent, RoomUpdatedEvent},
+        Event, Publisher,
+    },
+    jwt::Authorized,
+    pagination::{Page, PageQuery},
+    permissions::{RoomsRead, RoomsWrite},
+    response::{Response, ResponseBody},
     roles::Role,
+    validation::{field_error, validate, validate_with, ValidationErrors},
 };
-use mongodb::bson::oid::ObjectId;
-use rocket::{
-    async_trait,
-    http::Status,
-    response::status::{Created, Custom},
-};
-use std::str::FromStr;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use rocket::{async_trait, response::status::Created};
+use serde_json::{json, Value};
+use std::{env, str::FromStr};
 
 use crate::{
-    entities::{buildings::Building, floors::Floor, rooms::Room},
-    models::{buildings::BuildingModel, floors::FloorModel, rooms::RoomModel},
-    repositories::{buildings::BuildingRepoTrait, floors::FloorRepoTrait, rooms::RoomRepoTrait},
+    entities::{
+        buildings::Building,
+        floors::Floor,
+        rooms::Room,
+    },
+    models::{
+        buildings::BuildingModel,
+        floors::{FloorFilterModel, FloorModel},
+        rooms::{RoomAvailabilityModel, RoomFilterModel, RoomModel},
+    },
+    repositories::{
+        bookings::BookingRepoTrait, buildings::BuildingRepoTrait, floors::FloorRepoTrait,
+        rooms::RoomRepoTrait,
+    },
+    views::{
+        buildings::{BuildingTreeView, BuildingView},
+        floors::FloorView,
+        rooms::{RoomOccupancyView, RoomView},
+    },
 };
 
 #[async_trait]
 pub trait RoomUseCaseTrait: Send + Sync {
     async fn new_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: BuildingModel,
-    ) -> Result<Created<String>, Custom<String>>;
+    ) -> Result<Created<Response<BuildingView>>, AppError>;
     async fn new_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: FloorModel,
-    ) -> Result<Created<String>, Custom<String>>;
+    ) -> Result<Created<Response<FloorView>>, AppError>;
     async fn new_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: RoomModel,
-    ) -> Result<Created<String>, Custom<String>>;
-
+    ) -> Result<Created<Response<RoomView>>, AppError>;
     async fn fetch_all_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
-    ) -> Result<String, Custom<String>>;
-
+        key: Result<Authorized<RoomsRead>, AppError>,
+        query: PageQuery,
+    ) -> Result<Response<Page<BuildingView>>, AppError>;
     async fn fetch_all_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
-    ) -> Result<String, Custom<String>>;
+        key: Result<Authorized<RoomsRead>, AppError>,
+        filter: FloorFilterModel,
+        query: PageQuery,
+    ) -> Result<Response<Page<FloorView>>, AppError>;
     async fn fetch_all_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
-    ) -> Result<String, Custom<String>>;
-
+        key: Result<Authorized<RoomsRead>, AppError>,
+        filter: RoomFilterModel,
+        query: PageQuery,
+    ) -> Result<Response<Page<RoomOccupancyView>>, AppError>;
     async fn fetch_one_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsRead>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
+    ) -> Result<Response<BuildingView>, AppError>;
     async fn fetch_one_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsRead>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
-
+    ) -> Result<Response<FloorView>, AppError>;
     async fn fetch_one_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsRead>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
-
+    ) -> Result<Response<RoomOccupancyView>, AppError>;
     async fn update_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: BuildingModel,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
-
+    ) -> Result<Response<BuildingView>, AppError>;
     async fn update_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: FloorModel,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
+    ) -> Result<Response<FloorView>, AppError>;
     async fn update_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: RoomModel,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
-
+    ) -> Result<Response<RoomView>, AppError>;
     async fn delete_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
+    ) -> Result<Response<String>, AppError>;
     async fn delete_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
-
+    ) -> Result<Response<String>, AppError>;
     async fn delete_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>;
+    ) -> Result<Response<String>, AppError>;
+    async fn fetch_available_rooms(
+        &self,
+        key: Result<Authorized<RoomsRead>, AppError>,
+        model: RoomAvailabilityModel,
+    ) -> Result<Response<Vec<RoomView>>, AppError>;
+    async fn fetch_building_floors(
+        &self,
+        key: Result<Authorized<RoomsRead>, AppError>,
+        _id: &str,
+    ) -> Result<Response<Vec<FloorView>>, AppError>;
+    async fn fetch_floor_rooms(
+        &self,
+        key: Result<Authorized<RoomsRead>, AppError>,
+        _id: &str,
+    ) -> Result<Response<Vec<RoomOccupancyView>>, AppError>;
+    async fn fetch_building_tree(
+        &self,
+        key: Result<Authorized<RoomsRead>, AppError>,
+        _id: &str,
+    ) -> Result<Response<BuildingTreeView>, AppError>;
+}
+const BUILDING_SORT_FIELDS: [&str; 1] = ["name"];
+const FLOOR_SORT_FIELDS: [&str; 1] = ["name"];
+const ROOM_SORT_FIELDS: [&str; 3] = ["room_number", "name", "price"];
+
+/// What deleting a building or floor does to the floors and rooms under it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeletePolicy {
+    /// Refuse with 409 while children exist.
+    Restrict,
+    /// Delete the children too, announcing every deleted room.
+    Cascade,
+}
+impl DeletePolicy {
+    /// Reads `ROOMS_DELETE_POLICY` (`restrict` or `cascade`), defaulting to `restrict`
+    /// when it is not set. Any other value is an error, so a typo cannot quietly turn
+    /// cascading off.
+    pub fn from_env() -> Result<Self, String> {
+        dotenv().ok();
+        match env::var("ROOMS_DELETE_POLICY") {
+            Err(_) => Ok(DeletePolicy::Restrict),
+            Ok(policy) => match policy.as_str() {
+                "restrict" => Ok(DeletePolicy::Restrict),
+                "cascade" => Ok(DeletePolicy::Cascade),
+                other => Err(format!(
+                    "ROOMS_DELETE_POLICY must be restrict or cascade, got {}",
+                    other
+                )),
+            },
+        }
+    }
 }
+
 pub struct RoomUseCase {
     building_repo: Box<dyn BuildingRepoTrait>,
     floor_repo: Box<dyn FloorRepoTrait>,
     room_repo: Box<dyn RoomRepoTrait>,
+    booking_repo: Box<dyn BookingRepoTrait>,
+    publisher: Publisher,
+    delete_policy: DeletePolicy,
 }
 impl RoomUseCase {
     pub fn new(
         building_repo: Box<dyn BuildingRepoTrait>,
         floor_repo: Box<dyn FloorRepoTrait>,
         room_repo: Box<dyn RoomRepoTrait>,
+        booking_repo: Box<dyn BookingRepoTrait>,
+        publisher: Publisher,
+        delete_policy: DeletePolicy,
     ) -> Self {
         RoomUseCase {
             building_repo,
             floor_repo,
             room_repo,
+            booking_repo,
+            publisher,
+            delete_policy,
         }
     }
-    pub fn validate_building(&self, model: BuildingModel) -> Vec<String> {
-        let errors = [match model.name == "" {
-            true => Some("Name must be provided".to_string()),
-            false => None,
-        }]
-        .to_vec();
-        errors
+    async fn publish<E: Event>(&self, event: E) {
+        if let Err(err) = self.publisher.publish(&event).await {
+            println!("Cannot publish {}: {}", E::SUBJECT, err);
+        }
+    }
+    async fn validate_building_exists(
+        &self,
+        building_id: &str,
+    ) -> Result<ValidationErrors, AppError> {
+        let mut errors = ValidationErrors::new();
+        if let Ok(building_id) = ObjectId::parse_str(building_id) {
+            if self.building_repo.find_one(building_id).await?.is_none() {
+                errors.add(
+                    "building_id",
+                    field_error("exists", "building does not exist"),
+                );
+            }
+        }
+        Ok(errors)
+    }
+    async fn validate_floor_exists(&self, floor_id: &str) -> Result<ValidationErrors, AppError> {
+        let mut errors = ValidationErrors::new();
+        if let Ok(floor_id) = ObjectId::parse_str(floor_id) {
+            if self.floor_repo.find_one(floor_id).await?.is_none() {
+                errors.add("floor_id", field_error("exists", "floor does not exist"));
+            }
+        }
+        Ok(errors)
+    }
+    /// Deletes `rooms` for a cascade, refusing with 409 before deleting any of them
+    /// while one still has a booking that has not ended.
+    async fn delete_rooms(&self, rooms: Vec<Room>) -> Result<(), AppError> {
+        let ids = rooms
             .iter()
-            .map(|error| error.to_owned().to_owned())
-            .flatten()
-            .collect::<Vec<String>>()
+            .map(|room| room._id.to_hex())
+            .collect::<Vec<String>>();
+        let bookings = self
+            .booking_repo
+            .find_active_by_rooms(ids.clone(), DateTime::now())
+            .await?;
+        if !bookings.is_empty() {
+            let mut booked: Vec<Value> = Vec::new();
+            for booking in bookings.iter() {
+                for room in booking.rooms.iter().filter(|room| ids.contains(room)) {
+                    booked.push(json!({"room": room, "reservation_id": booking._id.to_hex()}));
+                }
+            }
+            return Err(AppError::Conflict {
+                message: "Rooms still have active bookings".to_string(),
+                details: Some(Value::Array(booked)),
+            });
+        }
+        for room in rooms {
+            self.room_repo.delete(room._id).await?;
+            self.publish(RoomDeletedEvent {
+                id: room._id.to_hex(),
+                version: DateTime::now().timestamp_millis(),
+            })
+            .await;
+        }
+        Ok(())
     }
-    pub fn validate_floor(&self, model: FloorModel) -> Vec<String> {
-        let errors = [
-            match ObjectId::from_str(&model.building_id).is_err() {
-                true => Some("building must be provided".to_string()),
-                false => None,
-            },
-            match model.name == "" {
-                true => Some("building name must be provided".to_string()),
-                false => None,
-            },
-        ]
-        .to_vec();
-        errors
+    /// Applies the delete policy to the floors of a building about to be deleted.
+    async fn delete_floors(&self, floors: Vec<Floor>) -> Result<(), AppError> {
+        if floors.is_empty() {
+            return Ok(());
+        }
+        match self.delete_policy {
+            DeletePolicy::Restrict => Err(AppError::conflict("Building still has floors")),
+            DeletePolicy::Cascade => {
+                let floor_ids = floors
+                    .iter()
+                    .map(|floor| floor._id)
+                    .collect::<Vec<ObjectId>>();
+                self.delete_rooms(self.room_repo.find_by_floors(floor_ids).await?)
+                    .await?;
+                for floor in floors {
+                    self.floor_repo.delete(floor._id).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+    /// Applies the delete policy to the rooms of a floor about to be deleted.
+    async fn delete_floor_rooms(&self, floor_id: ObjectId) -> Result<(), AppError> {
+        let rooms = self.room_repo.find_by_floors(vec![floor_id]).await?;
+        if rooms.is_empty() {
+            return Ok(());
+        }
+        match self.delete_policy {
+            DeletePolicy::Restrict => Err(AppError::conflict("Floor still has rooms")),
+            DeletePolicy::Cascade => self.delete_rooms(rooms).await,
+        }
+    }
+    /// Occupancy is derived when the rooms are read rather than stored on the room,
+    /// so it never goes stale as reservations start and end.
+    async fn occupancy(&self, rooms: Vec<Room>) -> Result<Vec<RoomOccupancyView>, AppError> {
+        let now = DateTime::now();
+        let ids = rooms
             .iter()
-            .map(|error| error.to_owned().to_owned())
-            .flatten()
-            .collect::<Vec<String>>()
+            .map(|room| room._id.to_hex())
+            .collect::<Vec<String>>();
+        let bookings = self.booking_repo.find_active_by_rooms(ids, now).await?;
+        Ok(rooms
+            .into_iter()
+            .map(|room| {
+                let id = room._id.to_hex();
+                let room_bookings = bookings
+                    .iter()
+                    .filter(|booking| booking.rooms.contains(&id))
+                    .collect::<Vec<_>>();
+                let current_reservation_id = room_bookings
+                    .iter()
+                    .find(|booking| booking.reservation_start_date <= now)
+                    .map(|booking| booking._id.to_hex());
+                let next_booking_start = room_bookings
+                    .iter()
+                    .map(|booking| booking.reservation_start_date)
+                    .filter(|start| *start > now)
+                    .min()
+                    .and_then(|start| start.try_to_rfc3339_string().ok());
+                RoomOccupancyView {
+                    room: room.into(),
+                    current_reservation_id,
+                    next_booking_start,
+                }
+            })
+            .collect::<Vec<RoomOccupancyView>>())
     }
-    pub fn validate_room(&self, model: RoomModel) -> Vec<String> {
-        let errors = [
-            match ObjectId::from_str(&model.floor_id).is_err() {
-                true => Some("floor must be provided".to_string()),
-                false => None,
-            },
-            match model.room_number == "" {
-                true => Some("room number must be provided".to_string()),
-                false => None,
-            },
-            match model.price <= 0.0 {
-                true => Some("room price must be provided".to_string()),
-                false => None,
-            },
-        ]
-        .to_vec();
+    /// The rules of `GET /rooms` that span two query parameters.
+    pub fn validate_room_filter(&self, filter: &RoomFilterModel) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        self.validate_price_range(filter.min_price, filter.max_price, &mut errors);
         errors
-            .iter()
-            .map(|error| error.to_owned().to_owned())
-            .flatten()
-            .collect::<Vec<String>>()
+    }
+    /// The rules of `GET /rooms/available` that span two query parameters.
+    pub fn validate_availability(&self, model: &RoomAvailabilityModel) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        let start = DateTime::parse_rfc3339_str(&model.start);
+        let end = DateTime::parse_rfc3339_str(&model.end);
+        if let (Ok(start), Ok(end)) = (start, end) {
+            if start >= end {
+                errors.add("end", field_error("date_order", "start must be before end"));
+            }
+        }
+        self.validate_price_range(model.min_price, model.max_price, &mut errors);
+        errors
+    }
+    fn validate_price_range(
+        &self,
+        min_price: Option<f64>,
+        max_price: Option<f64>,
+        errors: &mut ValidationErrors,
+    ) {
+        if let (Some(min_price), Some(max_price)) = (min_price, max_price) {
+            if min_price > max_price {
+                errors.add(
+                    "max_price",
+                    field_error("price_order", "min price must not exceed max price"),
+                );
+            }
+        }
     }
 }
 #[async_trait]
 impl RoomUseCaseTrait for RoomUseCase {
     async fn new_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: BuildingModel,
-    ) -> Result<Created<String>, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let errors = self.validate_building(model.clone());
-                match errors.len() > 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let building = Building {
-                            _id: ObjectId::new(),
-                            name: model.name,
-                            create_by: ObjectId::from_str(&_k.claims.subject_id.as_str()).unwrap(),
-                        };
-                        let insert_result = self.building_repo.add(building).await;
-                        let result = self
-                            .building_repo
-                            .find_one(insert_result.inserted_id.as_object_id().unwrap())
-                            .await
-                            .unwrap();
-                        let response = Response {
-                            body: ResponseBody::<Building>::Data(result),
-                        };
-                        Ok(Created::new("").tagged_body(serde_json::to_string(&response).unwrap()))
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+    ) -> Result<Created<Response<BuildingView>>, AppError> {
+        let Authorized { jwt: _k, .. } = key?;
+        validate(&model)?;
+        let building = Building {
+            _id: ObjectId::new(),
+            name: model.name,
+            create_by: ObjectId::from_str(_k.claims.subject_id.as_str())?,
+        };
+        let insert_result = self.building_repo.add(building).await?;
+        let result = self
+            .building_repo
+            .find_one(insert_result.inserted_id.as_object_id().unwrap())
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Building".to_string()))?;
+        let response = Response {
+            body: ResponseBody::<BuildingView>::Data(result.into()),
+        };
+        Ok(Created::new("").body(response))
     }
     async fn new_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: FloorModel,
-    ) -> Result<Created<String>, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let errors = self.validate_floor(model.clone());
-                match errors.len() > 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let floor = Floor {
-                            _id: ObjectId::new(),
-                            building_id: ObjectId::from_str(model.building_id.as_str()).unwrap(),
-                            name: model.name,
-                            create_by: ObjectId::from_str(_k.claims.subject_id.as_str()).unwrap(),
-                        };
-                        let insert_result = self.floor_repo.add(floor).await;
-                        let result = self
-                            .floor_repo
-                            .find_one(insert_result.inserted_id.as_object_id().unwrap())
-                            .await
-                            .unwrap();
-                        let response = Response {
-                            body: ResponseBody::<Floor>::Data(result),
-                        };
-                        Ok(Created::new("").tagged_body(serde_json::to_string(&response).unwrap()))
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+    ) -> Result<Created<Response<FloorView>>, AppError> {
+        let Authorized { jwt: _k, .. } = key?;
+        validate_with(
+            &model,
+            self.validate_building_exists(&model.building_id).await?,
+        )?;
+        let floor = Floor {
+            _id: ObjectId::new(),
+            building_id: ObjectId::from_str(model.building_id.as_str())?,
+            name: model.name,
+            create_by: ObjectId::from_str(_k.claims.subject_id.as_str())?,
+        };
+        let insert_result = self.floor_repo.add(floor).await?;
+        let result = self
+            .floor_repo
+            .find_one(insert_result.inserted_id.as_object_id().unwrap())
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Floor".to_string()))?;
+        let response = Response {
+            body: ResponseBody::<FloorView>::Data(result.into()),
+        };
+        Ok(Created::new("").body(response))
     }
     async fn new_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: RoomModel,
-    ) -> Result<Created<String>, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let errors = self.validate_room(model.clone());
-                match errors.len() > 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let room = Room {
-                            _id: ObjectId::new(),
-                            floor_id: ObjectId::from_str(model.floor_id.as_str()).unwrap(),
-                            room_number: model.room_number,
-                            name: model.name,
-                            price: model.price,
-                            is_reservation: false,
-                            create_by: ObjectId::from_str(_k.claims.subject_id.as_str()).unwrap(),
-                        };
-                        let insert_result = self.room_repo.add(room).await;
-                        let result = self
-                            .room_repo
-                            .find_one(insert_result.inserted_id.as_object_id().unwrap())
-                            .await
-                            .unwrap();
-                        let response = Response {
-                            body: ResponseBody::<Room>::Data(result),
-                        };
-                        Ok(Created::new("").tagged_body(serde_json::to_string(&response).unwrap()))
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+    ) -> Result<Created<Response<RoomView>>, AppError> {
+        let Authorized { jwt: _k, .. } = key?;
+        validate_with(&model, self.validate_floor_exists(&model.floor_id).await?)?;
+        let room = Room {
+            _id: ObjectId::new(),
+            floor_id: ObjectId::from_str(model.floor_id.as_str())?,
+            room_number: model.room_number,
+            name: model.name,
+            price: model.price,
+            create_by: ObjectId::from_str(_k.claims.subject_id.as_str())?,
+        };
+        let insert_result = self.room_repo.add(room).await?;
+        let result = self
+            .room_repo
+            .find_one(insert_result.inserted_id.as_object_id().unwrap())
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Room".to_string()))?;
+        self.publish(RoomCreatedEvent {
+            id: result._id.to_hex(),
+            floor_id: result.floor_id.to_hex(),
+            room_number: result.room_number.clone(),
+            name: result.name.clone(),
+            price: result.price,
+            version: DateTime::now().timestamp_millis(),
+        })
+        .await;
+        let response = Response {
+            body: ResponseBody::<RoomView>::Data(result.into()),
+        };
+        Ok(Created::new("").body(response))
     }
 
     async fn fetch_all_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let results = self.building_repo.find_all().await;
-                match results.len() == 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(
-                                "Not Found Building".to_string(),
-                            ),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::NotFound.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let response = Response {
-                            body: ResponseBody::<Vec<Building>>::Data(results),
-                        };
-                        Ok(serde_json::to_string(&response).unwrap())
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+        key: Result<Authorized<RoomsRead>, AppError>,
+        query: PageQuery,
+    ) -> Result<Response<Page<BuildingView>>, AppError> {
+        key?;
+        let pagination = query.validate(&BUILDING_SORT_FIELDS)?;
+        let results = self.building_repo.find_all(&pagination).await?;
+        Ok(Response {
+            body: ResponseBody::<Page<BuildingView>>::Data(results.map(BuildingView::from)),
+        })
     }
 
     async fn fetch_all_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let results = self.floor_repo.find_all().await;
-                match results.len() == 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(
-                                "Not Found Floor".to_string(),
-                            ),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::NotFound.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let response = Response {
-                            body: ResponseBody::<Vec<Floor>>::Data(results),
-                        };
-                        Ok(serde_json::to_string(&response).unwrap())
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+        key: Result<Authorized<RoomsRead>, AppError>,
+        filter: FloorFilterModel,
+        query: PageQuery,
+    ) -> Result<Response<Page<FloorView>>, AppError> {
+        key?;
+        validate(&filter)?;
+        let pagination = query.validate(&FLOOR_SORT_FIELDS)?;
+        let results = self.floor_repo.find_all(filter, &pagination).await?;
+        Ok(Response {
+            body: ResponseBody::<Page<FloorView>>::Data(results.map(FloorView::from)),
+        })
     }
-
     async fn fetch_all_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let results = self.room_repo.find_all().await;
-                match results.len() == 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(
-                                "Not Found Floor".to_string(),
-                            ),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::NotFound.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let response = Response {
-                            body: ResponseBody::<Vec<Room>>::Data(results),
-                        };
-                        Ok(serde_json::to_string(&response).unwrap())
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+        key: Result<Authorized<RoomsRead>, AppError>,
+        filter: RoomFilterModel,
+        query: PageQuery,
+    ) -> Result<Response<Page<RoomOccupancyView>>, AppError> {
+        key?;
+        validate_with(&filter, self.validate_room_filter(&filter))?;
+        let pagination = query.validate(&ROOM_SORT_FIELDS)?;
+        let mut page = self.room_repo.find_all(filter, &pagination).await?;
+        let rooms = std::mem::take(&mut page.items);
+        let results = page.with_items(self.occupancy(rooms).await?);
+        Ok(Response {
+            body: ResponseBody::<Page<RoomOccupancyView>>::Data(results),
+        })
     }
 
     async fn fetch_one_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsRead>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let id = ObjectId::parse_str(_id);
-                match id.clone().is_err() {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error("Invalid id".to_string()),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let result = self
-                            .building_repo
-                            .find_one(ObjectId::from_str(_id).unwrap())
-                            .await;
-                        match result {
-                            Some(res) => {
-                                let response = Response {
-                                    body: ResponseBody::<Building>::Data(res),
-                                };
-                                Ok(serde_json::to_string(&response).unwrap())
-                            }
-                            None => {
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Not Found Building".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::NotFound.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
+    ) -> Result<Response<BuildingView>, AppError> {
+        key?;
+        let id = ObjectId::parse_str(_id)?;
+        match self.building_repo.find_one(id).await? {
+            Some(res) => Ok(Response {
+                body: ResponseBody::<BuildingView>::Data(res.into()),
+            }),
+            None => Err(AppError::NotFound("Not Found Building".to_string())),
         }
     }
 
     async fn fetch_one_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsRead>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let id = ObjectId::parse_str(_id);
-                match id.clone().is_err() {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error("Invalid id".to_string()),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let result = self
-                            .floor_repo
-                            .find_one(ObjectId::from_str(_id).unwrap())
-                            .await;
-                        match result {
-                            Some(res) => {
-                                let response = Response {
-                                    body: ResponseBody::<Floor>::Data(res),
-                                };
-                                Ok(serde_json::to_string(&response).unwrap())
-                            }
-                            None => {
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Not Found Floor".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::NotFound.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
+    ) -> Result<Response<FloorView>, AppError> {
+        key?;
+        let id = ObjectId::parse_str(_id)?;
+        match self.floor_repo.find_one(id).await? {
+            Some(res) => Ok(Response {
+                body: ResponseBody::<FloorView>::Data(res.into()),
+            }),
+            None => Err(AppError::NotFound("Not Found Floor".to_string())),
         }
     }
 
     async fn fetch_one_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsRead>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let id = ObjectId::parse_str(_id);
-                match id.clone().is_err() {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error("Invalid id".to_string()),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let result = self
-                            .room_repo
-                            .find_one(ObjectId::from_str(_id).unwrap())
-                            .await;
-                        match result {
-                            Some(res) => {
-                                let response = Response {
-                                    body: ResponseBody::<Room>::Data(res),
-                                };
-                                Ok(serde_json::to_string(&response).unwrap())
-                            }
-                            None => {
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Not Found Room".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::NotFound.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
+    ) -> Result<Response<RoomOccupancyView>, AppError> {
+        key?;
+        let id = ObjectId::parse_str(_id)?;
+        match self.room_repo.find_one(id).await? {
+            Some(res) => {
+                let res = self.occupancy(vec![res]).await?.remove(0);
+                Ok(Response {
+                    body: ResponseBody::<RoomOccupancyView>::Data(res),
+                })
             }
+            None => Err(AppError::NotFound("Not Found Room".to_string())),
         }
     }
 
     async fn update_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: BuildingModel,
         _id: &str,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let errors = self.validate_building(model.clone());
-                match errors.len() > 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let id = ObjectId::parse_str(_id);
-                        match id.is_err() {
-                            true => {
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Invalid id".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::BadRequest.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            },
-                            false => {
-                                let result = self.building_repo.find_one(id.clone().unwrap()).await;
-                                match result {
-                                    Some(building_db) => {
-                                        let building = Building {
-                                            _id: building_db._id,
-                                            name: model.name,
-                                            create_by: building_db.create_by,
-                                        };
-                                        self.building_repo
-                                            .update(building, id.clone().unwrap())
-                                            .await;
-                                        let result = self
-                                            .building_repo
-                                            .find_one(building_db._id)
-                                            .await
-                                            .unwrap();
-                                        let response = Response {
-                                            body: ResponseBody::<Building>::Data(result),
-                                        };
-                                        Ok(serde_json::to_string(&response).unwrap())
-                                    },
-                                    None => {
-                                        let response = ResponseError {
-                                            error: ResponseErrorBody::<String>::Error(
-                                                "Not Found Building".to_string(),
-                                            ),
-                                        };
-                                        Err(Custom(
-                                            Status {
-                                                code: Status::NotFound.code,
-                                            },
-                                            serde_json::to_string(&response).unwrap(),
-                                        ))
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+    ) -> Result<Response<BuildingView>, AppError> {
+        key?;
+        validate(&model)?;
+        let id = ObjectId::parse_str(_id)?;
+        let building_db = self
+            .building_repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Building".to_string()))?;
+        let building = Building {
+            _id: building_db._id,
+            name: model.name,
+            create_by: building_db.create_by,
+        };
+        self.building_repo.update(building, id).await?;
+        let result = self
+            .building_repo
+            .find_one(building_db._id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Building".to_string()))?;
+        Ok(Response {
+            body: ResponseBody::<BuildingView>::Data(result.into()),
+        })
     }
 
     async fn update_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: FloorModel,
         _id: &str,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let errors = self.validate_floor(model.clone());
-                match errors.len() > 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let id = ObjectId::parse_str(_id);
-                        match id.is_err() {
-                            true => {
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Invalid id".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::BadRequest.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            },
-                            false => {
-                                let result = self.floor_repo.find_one(id.clone().unwrap()).await;
-                                match result {
-                                    Some(floor_db) => {
-                                        let floor = Floor {
-                                            _id: floor_db._id,
-                                            building_id:ObjectId::from_str(model.building_id.as_str()).unwrap(),
-                                            name: model.name,
-                                            create_by: floor_db.create_by,
-                                        };
-                                        self.floor_repo
-                                            .update(floor, id.clone().unwrap())
-                                            .await;
-                                        let result = self
-                                            .floor_repo
-                                            .find_one(floor_db._id)
-                                            .await
-                                            .unwrap();
-                                        let response = Response {
-                                            body: ResponseBody::<Floor>::Data(result),
-                                        };
-                                        Ok(serde_json::to_string(&response).unwrap())
-                                    },
-                                    None => {
-                                        let response = ResponseError {
-                                            error: ResponseErrorBody::<String>::Error(
-                                                "Not Found Floor".to_string(),
-                                            ),
-                                        };
-                                        Err(Custom(
-                                            Status {
-                                                code: Status::NotFound.code,
-                                            },
-                                            serde_json::to_string(&response).unwrap(),
-                                        ))
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+    ) -> Result<Response<FloorView>, AppError> {
+        key?;
+        validate_with(
+            &model,
+            self.validate_building_exists(&model.building_id).await?,
+        )?;
+        let id = ObjectId::parse_str(_id)?;
+        let floor_db = self
+            .floor_repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Floor".to_string()))?;
+        let floor = Floor {
+            _id: floor_db._id,
+            building_id: ObjectId::from_str(model.building_id.as_str())?,
+            name: model.name,
+            create_by: floor_db.create_by,
+        };
+        self.floor_repo.update(floor, id).await?;
+        let result = self
+            .floor_repo
+            .find_one(floor_db._id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Floor".to_string()))?;
+        Ok(Response {
+            body: ResponseBody::<FloorView>::Data(result.into()),
+        })
     }
 
-
     async fn update_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         model: RoomModel,
         _id: &str,
-    ) -> Result<String, Custom<String>> {
-        match key {
-            Ok(_k) => {
-                let errors = self.validate_room(model.clone());
-                match errors.len() > 0 {
-                    true => {
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error(errors.join(",")),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    }
-                    false => {
-                        let id = ObjectId::parse_str(_id);
-                        match id.is_err() {
-                            true => {
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Invalid id".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::BadRequest.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            },
-                            false => {
-                                let result = self.room_repo.find_one(id.clone().unwrap()).await;
-                                match result {
-                                    Some(room_db) => {
-                                        let room =Room{
-                                            _id:room_db._id,
-                                            floor_id:ObjectId::from_str(model.floor_id.as_str()).unwrap(),
-                                            room_number:model.room_number,
-                                            name:model.name,
-                                            price:model.price,
-                                            is_reservation:room_db.is_reservation,
-                                            create_by:room_db.create_by
-                                        };
-                                        self.room_repo
-                                            .update(room, id.clone().unwrap())
-                                            .await;
-                                        let result = self
-                                            .room_repo
-                                            .find_one(room_db._id)
-                                            .await
-                                            .unwrap();
-                                        let response = Response {
-                                            body: ResponseBody::<Room>::Data(result),
-                                        };
-                                        Ok(serde_json::to_string(&response).unwrap())
-                                    },
-                                    None => {
-                                        let response = ResponseError {
-                                            error: ResponseErrorBody::<String>::Error(
-                                                "Not Found Room".to_string(),
-                                            ),
-                                        };
-                                        Err(Custom(
-                                            Status {
-                                                code: Status::NotFound.code,
-                                            },
-                                            serde_json::to_string(&response).unwrap(),
-                                        ))
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+    ) -> Result<Response<RoomView>, AppError> {
+        key?;
+        validate_with(&model, self.validate_floor_exists(&model.floor_id).await?)?;
+        let id = ObjectId::parse_str(_id)?;
+        let room_db = self
+            .room_repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Room".to_string()))?;
+        let room = Room {
+            _id: room_db._id,
+            floor_id: ObjectId::from_str(model.floor_id.as_str())?,
+            room_number: model.room_number,
+            name: model.name,
+            price: model.price,
+            create_by: room_db.create_by,
+        };
+        self.room_repo.update(room, id).await?;
+        let result = self
+            .room_repo
+            .find_one(room_db._id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Room".to_string()))?;
+        self.publish(RoomUpdatedEvent {
+            id: result._id.to_hex(),
+            floor_id: result.floor_id.to_hex(),
+            room_number: result.room_number.clone(),
+            name: result.name.clone(),
+            price: result.price,
+            version: DateTime::now().timestamp_millis(),
+        })
+        .await;
+        Ok(Response {
+            body: ResponseBody::<RoomView>::Data(result.into()),
+        })
     }
 
     async fn delete_building(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>{
-        match key {
-            Ok(_k)=>{
-                let id = ObjectId::parse_str(_id);
-                match id.is_err() {
-                    true=>{
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error("Invalid id".to_string()),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    },
-                    false=>{
-                        let result = self.building_repo.find_one(id.clone().unwrap()).await;
-                        match result {
-                            Some(res)=>{
-                                self.building_repo.delete(res._id).await;
-                                let response = Response {
-                                    body: ResponseBody::<String>::Data(
-                                        "Delete Success".to_string(),
-                                    ),
-                                };
-                                Ok(serde_json::to_string(&response).unwrap())
-                            },
-                            None=>{
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Not Found Building".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::NotFound.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            }
-                        }
-                    }
-                }
-            },
-            _ =>{ 
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+    ) -> Result<Response<String>, AppError> {
+        key?;
+        let id = ObjectId::parse_str(_id)?;
+        let res = self
+            .building_repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Building".to_string()))?;
+        let floors = self.floor_repo.find_by_building(res._id).await?;
+        self.delete_floors(floors).await?;
+        self.building_repo.delete(res._id).await?;
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Delete Success".to_string()),
+        })
     }
 
     async fn delete_floor(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>{
-        match key {
-            Ok(_k)=>{
-                let id = ObjectId::parse_str(_id);
-                match id.is_err() {
-                    true=>{
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error("Invalid id".to_string()),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    },
-                    false=>{
-                        let result = self.floor_repo.find_one(id.clone().unwrap()).await;
-                        match result {
-                            Some(res)=>{
-                                self.floor_repo.delete(res._id).await;
-                                let response = Response {
-                                    body: ResponseBody::<String>::Data(
-                                        "Delete Success".to_string(),
-                                    ),
-                                };
-                                Ok(serde_json::to_string(&response).unwrap())
-                            },
-                            None=>{
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Not Found Floor".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::NotFound.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            }
-                        }
-                    }
-                }
-            },
-            _ =>{ 
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
-            }
-        }
+    ) -> Result<Response<String>, AppError> {
+        key?;
+        let id = ObjectId::parse_str(_id)?;
+        let res = self
+            .floor_repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Floor".to_string()))?;
+        self.delete_floor_rooms(res._id).await?;
+        self.floor_repo.delete(res._id).await?;
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Delete Success".to_string()),
+        })
     }
 
     async fn delete_room(
         &self,
-        key: Result<JWT, ResponseError<String>>,
+        key: Result<Authorized<RoomsWrite>, AppError>,
         _id: &str,
-    ) -> Result<String, Custom<String>>{
-        match key {
-            Ok(_k)=>{
-                let id = ObjectId::parse_str(_id);
-                match id.is_err() {
-                    true=>{
-                        let response = ResponseError {
-                            error: ResponseErrorBody::<String>::Error("Invalid id".to_string()),
-                        };
-                        Err(Custom(
-                            Status {
-                                code: Status::BadRequest.code,
-                            },
-                            serde_json::to_string(&response).unwrap(),
-                        ))
-                    },
-                    false=>{
-                        let result = self.room_repo.find_one(id.clone().unwrap()).await;
-                        match result {
-                            Some(res)=>{
-                                self.room_repo.delete(res._id).await;
-                                let response = Response {
-                                    body: ResponseBody::<String>::Data(
-                                        "Delete Success".to_string(),
-                                    ),
-                                };
-                                Ok(serde_json::to_string(&response).unwrap())
-                            },
-                            None=>{
-                                let response = ResponseError {
-                                    error: ResponseErrorBody::<String>::Error(
-                                        "Not Found Room".to_string(),
-                                    ),
-                                };
-                                Err(Custom(
-                                    Status {
-                                        code: Status::NotFound.code,
-                                    },
-                                    serde_json::to_string(&response).unwrap(),
-                                ))
-                            }
-                        }
-                    }
-                }
-            },
-            _ =>{ 
-                let response = ResponseError {
-                    error: ResponseErrorBody::<String>::Error("Unauthorize".to_string()),
-                };
-                Err(Custom(
-                    Status {
-                        code: Status::Unauthorized.code,
-                    },
-                    serde_json::to_string(&response).unwrap(),
-                ))
+    ) -> Result<Response<String>, AppError> {
+        key?;
+        let id = ObjectId::parse_str(_id)?;
+        let res = self
+            .room_repo
+            .find_one(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not Found Room".to_string()))?;
+        self.room_repo.delete(res._id).await?;
+        self.publish(RoomDeletedEvent {
+            id: res._id.to_hex(),
+            version: DateTime::now().timestamp_millis(),
+        })
+        .await;
+        Ok(Response {
+            body: ResponseBody::<String>::Data("Delete Success".to_string()),
+        })
+    }
+
+    async fn fetch_available_rooms(
+        &self,
+        key: Result<Authorized<RoomsRead>, AppError>,
+        model: RoomAvailabilityModel,
+    ) -> Result<Response<Vec<RoomView>>, AppError> {
+        key?;
+        validate_with(&model, self.validate_availability(&model))?;
+        let start = DateTime::parse_rfc3339_str(&model.start).unwrap();
+        let end = DateTime::parse_rfc3339_str(&model.end).unwrap();
+        let floor_ids = match model.building_id {
+            Some(building_id) => Some(
+                self.floor_repo
+                    .find_by_building(ObjectId::parse_str(building_id)?)
+                    .await?
+                    .iter()
+                    .map(|floor| floor._id)
+                    .collect::<Vec<ObjectId>>(),
+            ),
+            None => None,
+        };
+        let excluded = self
+            .booking_repo
+            .find_overlapping(start, end)
+            .await?
+            .into_iter()
+            .flat_map(|booking| booking.rooms)
+            .flat_map(|room| ObjectId::parse_str(room).ok())
+            .collect::<Vec<ObjectId>>();
+        let results = self
+            .room_repo
+            .find_available(floor_ids, excluded, model.min_price, model.max_price)
+            .await?;
+        Ok(Response {
+            body: ResponseBody::<Vec<RoomView>>::Data(
+                results.into_iter().map(RoomView::from).collect(),
+            ),
+        })
+    }
+
+    async fn fetch_building_floors(
+        &self,
+        key: Result<Authorized<RoomsRead>, AppError>,
+        _id: &str,
+    ) -> Result<Response<Vec<FloorView>>, AppError> {
+        key?;
+        let id = ObjectId::parse_str(_id)?;
+        match self.building_repo.find_one(id).await? {
+            Some(building) => {
+                let results = self.floor_repo.find_by_building(building._id).await?;
+                Ok(Response {
+                    body: ResponseBody::<Vec<FloorView>>::Data(
+                        results.into_iter().map(FloorView::from).collect(),
+                    ),
+                })
+            }
+            None => Err(AppError::NotFound("Not Found Building".to_string())),
+        }
+    }
+    async fn fetch_floor_rooms(
+        &self,
+        key: Result<Authorized<RoomsRead>, AppError>,
+        _id: &str,
+    ) -> Result<Response<Vec<RoomOccupancyView>>, AppError> {
+        key?;
+        let id = ObjectId::parse_str(_id)?;
+        match self.floor_repo.find_one(id).await? {
+            Some(floor) => {
+                let rooms = self.room_repo.find_by_floors(vec![floor._id]).await?;
+                let results = self.occupancy(rooms).await?;
+                Ok(Response {
+                    body: ResponseBody::<Vec<RoomOccupancyView>>::Data(results),
+                })
             }
+            None => Err(AppError::NotFound("Not Found Floor".to_string())),
+        }
+    }
+    async fn fetch_building_tree(
+        &self,
+        key: Result<Authorized<RoomsRead>, AppError>,
+        _id: &str,
+    ) -> Result<Response<BuildingTreeView>, AppError> {
+        key?;
+        let id = ObjectId::parse_str(_id)?;
+        match self.building_repo.find_tree(id).await? {
+            Some(result) => Ok(Response {
+                body: ResponseBody::<BuildingTreeView>::Data(result.into()),
+            }),
+            None => Err(AppError::NotFound("Not Found Building".to_string())),
         }
     }
 }
@@ -0,0 +1,59 @@
+use serde::Serialize;
+
+use crate::entities::buildings::{Building, BuildingTree, FloorTree};
+
+use super::rooms::RoomView;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BuildingView {
+    pub id: String,
+    pub name: String,
+    pub create_by: String,
+}
+impl From<Building> for BuildingView {
+    fn from(building: Building) -> Self {
+        BuildingView {
+            id: building._id.to_hex(),
+            name: building.name,
+            create_by: building.create_by.to_hex(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BuildingTreeView {
+    pub id: String,
+    pub name: String,
+    pub create_by: String,
+    pub floors: Vec<FloorTreeView>,
+}
+impl From<BuildingTree> for BuildingTreeView {
+    fn from(tree: BuildingTree) -> Self {
+        BuildingTreeView {
+            id: tree._id.to_hex(),
+            name: tree.name,
+            create_by: tree.create_by.to_hex(),
+            floors: tree.floors.into_iter().map(FloorTreeView::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FloorTreeView {
+    pub id: String,
+    pub building_id: String,
+    pub name: String,
+    pub create_by: String,
+    pub rooms: Vec<RoomView>,
+}
+impl From<FloorTree> for FloorTreeView {
+    fn from(floor: FloorTree) -> Self {
+        FloorTreeView {
+            id: floor._id.to_hex(),
+            building_id: floor.building_id.to_hex(),
+            name: floor.name,
+            create_by: floor.create_by.to_hex(),
+            rooms: floor.rooms.into_iter().map(RoomView::from).collect(),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+use crate::entities::floors::Floor;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FloorView {
+    pub id: String,
+    pub building_id: String,
+    pub name: String,
+    pub create_by: String,
+}
+impl From<Floor> for FloorView {
+    fn from(floor: Floor) -> Self {
+        FloorView {
+            id: floor._id.to_hex(),
+            building_id: floor.building_id.to_hex(),
+            name: floor.name,
+            create_by: floor.create_by.to_hex(),
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod buildings;
+pub mod floors;
+pub mod rooms;
@@ -0,0 +1,35 @@
+use serde::Serialize;
+
+use crate::entities::rooms::Room;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RoomView {
+    pub id: String,
+    pub floor_id: String,
+    pub room_number: String,
+    pub name: String,
+    pub price: f64,
+    pub create_by: String,
+}
+impl From<Room> for RoomView {
+    fn from(room: Room) -> Self {
+        RoomView {
+            id: room._id.to_hex(),
+            floor_id: room.floor_id.to_hex(),
+            room_number: room.room_number,
+            name: room.name,
+            price: room.price,
+            create_by: room.create_by.to_hex(),
+        }
+    }
+}
+
+/// A room together with its occupancy, worked out from the bookings the rooms
+/// service mirrors from reservation events.
+#[derive(Debug, Serialize, Clone)]
+pub struct RoomOccupancyView {
+    #[serde(flatten)]
+    pub room: RoomView,
+    pub current_reservation_id: Option<String>,
+    pub next_booking_start: Option<String>,
+}